@@ -3,13 +3,105 @@ use rayon::prelude::*;
 use wassily::prelude::*;
 
 use crate::background::*;
-use crate::color::{color_scale, palette_colors, sample_colors, ColorBy, ColorMode};
-use crate::common::{Controls, CurveDirection, CurveStyle, HEIGHT, SEED, WIDTH};
-use crate::dot::DotStyle;
+use crate::color::{
+    color_scale, gradient_colors, palette_colors, sample_colors, to_color, ColorBy, ColorMode,
+    Interpolation,
+};
+use crate::common::{Controls, CurveDirection, CurveStyle, Integrator, HEIGHT, SEED, WIDTH};
+use crate::density::{DensityControls, DensitySource};
+use crate::dot::{glyph_path, sprite, DotStyle, Tints};
 use crate::extrude::ExtrudeDirection;
-use crate::field::Field;
+use crate::field::{Field, SpatialGrid};
 use crate::gradient::paint_lg;
+use crate::isoline::ScalarGrid;
+use crate::layer::Blend;
+use crate::magnitude::{MagnitudeSource, Modulation};
 use crate::noise::*;
+use crate::spline::{resample, smooth, Smoothing};
+use crate::stops::{Mask, Stops};
+use crate::stroke::StrokeMode;
+use crate::symmetry::{SymmetricNoise, Symmetry};
+
+/// How many pieces the curves are split into for parallel rendering. Each
+/// piece is drawn on its own layer.
+const RENDER_CHUNKS: usize = 16;
+
+/// Turbulence and symmetry, applied to a field's noise.
+fn distort(
+    controls: &Controls,
+    w: u32,
+    h: u32,
+    symmetry: Symmetry,
+    noise_function: Box<dyn NoiseFn<f64, 2>>,
+) -> Box<dyn NoiseFn<f64, 2>> {
+    // Optionally distort the field's input coordinates with Perlin turbulence.
+    let noise_function: Box<dyn NoiseFn<f64, 2>> = if controls.turbulence.enabled {
+        Box::new(
+            Turbulence::<_, Perlin>::new(noise_function)
+                .set_frequency(controls.turbulence.frequency as f64)
+                .set_power(controls.turbulence.power as f64)
+                .set_roughness(controls.turbulence.roughness as usize),
+        )
+    } else {
+        noise_function
+    };
+    // Fold the sample points last, so turbulence is mirrored along with
+    // the rest of the field.
+    if symmetry.is_off() {
+        noise_function
+    } else {
+        let s = controls.noise_controls.noise_scale as f64;
+        Box::new(SymmetricNoise {
+            source: noise_function,
+            symmetry,
+            scale: [s / w as f64, s / h as f64],
+        })
+    }
+}
+
+/// The noise image through `color_map` as an Image flow field samples it,
+/// for the Image magnitude source, the Image mask and Image density maps.
+fn field_image(
+    controls: &Controls,
+    w: u32,
+    h: u32,
+    symmetry: Symmetry,
+    color_map: crate::imgnoise::ColorMap,
+) -> Option<crate::imgnoise::FieldImage> {
+    let image = image_noise(controls, color_map)?;
+    let image = Box::new(crate::imgnoise::SharedImgNoise(image));
+    Some(crate::imgnoise::FieldImage {
+        noise: distort(controls, w, h, symmetry, image),
+        opts: NoiseOpts::with_wh(w, h).scales(controls.noise_controls.noise_scale),
+    })
+}
+
+/// The processed noise image through `color_map`, shared by Image flow
+/// fields and everything that samples the image alongside them.
+fn image_noise(
+    controls: &Controls,
+    color_map: crate::imgnoise::ColorMap,
+) -> Option<std::sync::Arc<crate::imgnoise::ImgNoise>> {
+    controls.image_noise.path.as_deref().and_then(|p| {
+        crate::imgnoise::cached_noise(
+            p,
+            color_map,
+            controls.image_noise.blur.max(0.0),
+            controls
+                .image_noise
+                .rotation
+                .unwrap_or(crate::imgnoise::Rotation::Deg0),
+        )
+    })
+}
+
+/// The color map the flow field reads the noise image through.
+fn color_map(controls: &Controls) -> crate::imgnoise::ColorMap {
+    controls
+        .image_noise
+        .color_map
+        .unwrap_or(crate::imgnoise::ColorMap::Lightness)
+}
 
 fn choose_flow(controls: &Controls, w: u32, h: u32) -> Field {
     let opts = NoiseOpts::with_wh(w, h)
@@ -104,21 +196,7 @@ fn choose_flow(controls: &Controls, w: u32, h: u32) -> Field {
                 Box::new(Curl::new(nf))
             }
             NoiseFunction::Image => {
-                let noise = controls.image_noise.path.as_deref().and_then(|p| {
-                    crate::imgnoise::cached_noise(
-                        p,
-                        controls
-                            .image_noise
-                            .color_map
-                            .unwrap_or(crate::imgnoise::ColorMap::Lightness),
-                        controls.image_noise.blur.max(0.0),
-                        controls
-                            .image_noise
-                            .rotation
-                            .unwrap_or(crate::imgnoise::Rotation::Deg0),
-                    )
-                });
-                match noise {
+                match image_noise(controls, color_map(controls)) {
                     Some(n) => Box::new(crate::imgnoise::SharedImgNoise(n)),
                     // No image chosen (or unreadable): a flat field.
                     None => Box::new(Constant::new(0.0)),
@@ -131,17 +209,8 @@ fn choose_flow(controls: &Controls, w: u32, h: u32) -> Field {
                 controls.sin_controls.yexp as f64,
             )),
     };
-    // Optionally distort the field's input coordinates with Perlin turbulence.
-    let noise_function: Box<dyn NoiseFn<f64, 2>> = if controls.turbulence.enabled {
-        Box::new(
-            Turbulence::<_, Perlin>::new(noise_function)
-                .set_frequency(controls.turbulence.frequency as f64)
-                .set_power(controls.turbulence.power as f64)
-                .set_roughness(controls.turbulence.roughness as usize),
-        )
-    } else {
-        noise_function
-    };
+    let symmetry = Symmetry::new(&controls.symmetry, w, h);
+    let noise_function = distort(controls, w, h, symmetry, noise_function);
     Field {
         noise_function,
         noise_opts: opts,
@@ -158,6 +227,101 @@ fn choose_flow(controls: &Controls, w: u32, h: u32) -> Field {
         } else {
             0.0
         },
+        symmetry,
+        mode: controls
+            .noise_controls
+            .field_mode
+            .unwrap_or(FieldMode::Value),
+        reverse: controls.noise_controls.reverse,
+        modulation: modulation(controls, w, h, symmetry),
+        integrator: controls.integrator.unwrap_or(Integrator::Euler),
+        tolerance: controls.tolerance,
+        momentum: controls.momentum,
+        stops: stops(controls, w, h, symmetry),
+    }
+}
+
+fn modulation(controls: &Controls, w: u32, h: u32, symmetry: Symmetry) -> Modulation {
+    let m = &controls.magnitude;
+    if m.step <= 0.0 && m.speed <= 0.0 && m.width <= 0.0 {
+        return Modulation::off();
+    }
+    let source = m.source.unwrap_or(MagnitudeSource::Noise);
+    Modulation {
+        source,
+        step: m.step,
+        speed: m.speed,
+        width: m.width,
+        invert: m.invert,
+        image: if source == MagnitudeSource::Image {
+            field_image(controls, w, h, symmetry, color_map(controls))
+        } else {
+            None
+        },
+        grad_norm: w as f32
+            / (controls.noise_controls.noise_scale * controls.noise_controls.noise_factor)
+                .max(0.01),
+    }
+}
+
+fn stops(controls: &Controls, w: u32, h: u32, symmetry: Symmetry) -> Stops {
+    let s = &controls.stops;
+    let mask = s.mask.unwrap_or(Mask::Off);
+    if !s.loops && !s.stall && mask == Mask::Off {
+        return Stops::off();
+    }
+    Stops {
+        loops: s.loops,
+        stall: s.stall,
+        mask,
+        threshold: s.threshold,
+        image: if mask == Mask::Image {
+            field_image(controls, w, h, symmetry, color_map(controls))
+        } else {
+            None
+        },
+    }
+}
+
+/// The local separation of evenly spaced streamlines, from the density map.
+fn density_map<'a>(
+    controls: &'a Controls,
+    density: &'a DensityControls,
+    flow: &'a Field,
+    w: f32,
+    h: f32,
+    sep: f32,
+) -> Box<dyn Fn(Point) -> f32 + 'a> {
+    match density.source.unwrap_or(DensitySource::Uniform) {
+        DensitySource::Uniform => Box::new(move |_: Point| sep),
+        DensitySource::Noise => Box::new(move |p: Point| {
+            density.sep(sep, noise2d_01(&flow.noise_function, &flow.noise_opts, p.x, p.y))
+        }),
+        DensitySource::Radial => {
+            let r_max = 0.5 * (w * w + h * h).sqrt();
+            Box::new(move |p: Point| {
+                let r = ((p.x - w / 2.0).powi(2) + (p.y - h / 2.0).powi(2)).sqrt();
+                density.sep(sep, 1.0 - r / r_max)
+            })
+        }
+        DensitySource::Image => {
+            // Always lightness, whatever color map steers the flow, sampled
+            // where the field samples the image so the two line up.
+            let img = field_image(
+                controls,
+                flow.width,
+                flow.height,
+                flow.symmetry,
+                crate::imgnoise::ColorMap::Lightness,
+            );
+            match img {
+                Some(img) => Box::new(move |p: Point| {
+                    let lum = 0.5 * img.value(p.x, p.y) + 0.5;
+                    density.sep(sep, 1.0 - lum)
+                }),
+                None => Box::new(move |_: Point| sep),
+            }
+        }
     }
 }
 
@@ -166,6 +330,22 @@ fn fade(c: Color, alpha: f32) -> Color {
     Color::from_rgba(c.red(), c.green(), c.blue(), c.alpha() * alpha).unwrap()
 }
 
+/// The layer's blend mode, which its curves paint with as well as the
+/// layer as a whole.
+fn blend_mode(controls: &Controls) -> BlendMode {
+    controls.layer.blend.unwrap_or(Blend::Normal).mode()
+}
+
+/// A solid paint for a curve: `color` at the curve opacity, in the layer's
+/// blend mode.
+fn ink(controls: &Controls, color: Color) -> Paint<'static> {
+    let mut paint = Paint::default();
+    paint.anti_alias = true;
+    paint.set_color(fade(color, controls.opacity));
+    paint.blend_mode = blend_mode(controls);
+    paint
+}
+
 fn gen_curve(flow: &Field, controls: &Controls, start: Point) -> Vec<Point> {
     match controls
         .curve_direction
@@ -176,12 +356,49 @@ fn gen_curve(flow: &Field, controls: &Controls, start: Point) -> Vec<Point> {
     }
 }
 
-/// Fill the channel between two neighboring curves, leaving `strip_gap` of
-/// it open; with AlongCurve coloring the fill glides along the strip.
+/// The partner of each point of `a` on its neighbor `b`: the point with the
+/// same index when the curves grew in step from a seed line, otherwise the
+/// nearest point of `b` within `max_d`.
+fn strip_partners(a: &[Point], b: &[Point], in_step: bool, max_d: f32) -> Vec<Option<Point>> {
+    if in_step {
+        return b.iter().take(a.len()).map(|q| Some(*q)).collect();
+    }
+    let mut grid = SpatialGrid::new(max_d);
+    for (k, q) in b.iter().enumerate() {
+        grid.insert(*q, k as u32);
+    }
+    a.iter().map(|p| grid.nearest(*p, max_d, |_| true).map(|x| x.0)).collect()
+}
+
+/// The curve each strip pairs with: the next seed on a seed line, otherwise
+/// the nearest later seed in the ordering, so every channel is filled once.
+/// Seeds farther apart than `max_d` are not paired.
+fn strip_neighbors(seeds: &[Point], in_step: bool, max_d: f32) -> Vec<Option<usize>> {
+    let n = seeds.len();
+    if in_step {
+        return (0..n)
+            .map(|i| (i + 1 < n && seeds[i].dist2(seeds[i + 1]) < max_d * max_d).then_some(i + 1))
+            .collect();
+    }
+    let mut grid = SpatialGrid::new(max_d);
+    for (k, q) in seeds.iter().enumerate() {
+        grid.insert(*q, k as u32);
+    }
+    (0..n)
+        .map(|i| {
+            grid.nearest(seeds[i], max_d, |k| k as usize > i)
+                .map(|x| x.1 as usize)
+        })
+        .collect()
+}
+
+/// Fill the channel between a curve and the partners of its points on a
+/// neighboring curve, leaving `strip_gap` of it open and skipping points
+/// with no partner; with AlongCurve coloring the fill glides along the strip.
 fn paint_strip(
     controls: &Controls,
     a: &[Point],
-    b: &[Point],
+    b: &[Option<Point>],
     c: Color,
     color_by: ColorBy,
     colors: &[Color],
@@ -194,42 +411,97 @@ fn paint_strip(
     let gap = controls.strip_gap.clamp(0.0, 0.9);
     let (lo, hi) = (gap / 2.0, 1.0 - gap / 2.0);
     let lp = |i: usize, t: f32| {
-        pt(
-            a[i].x + t * (b[i].x - a[i].x),
-            a[i].y + t * (b[i].y - a[i].y),
-        )
+        let q = b[i].expect("strip partner checked before use");
+        pt(a[i].x + t * (q.x - a[i].x), a[i].y + t * (q.y - a[i].y))
     };
     // The strip is painted as a run of slightly overlapping quads rather
     // than one big polygon: diverging or crossing curve pairs would make a
     // single polygon self-intersect and cancel its own fill.
     for i in 0..n - 1 {
         let j = (i + 2).min(n - 1);
+        if b[i].is_none() || b[j].is_none() {
+            continue;
+        }
         let color = if color_by == ColorBy::AlongCurve {
             let t = i as f32 / (n - 2).max(1) as f32;
-            sample_colors(colors, t)
+            sample_colors(colors, t, blend_space(controls))
         } else {
             c
         };
         let quad = [lp(i, lo), lp(j, lo), lp(j, hi), lp(i, hi)];
         Shape::new()
             .points(&quad)
-            .fill_color(fade(color, controls.opacity))
+            .fill_paint(&ink(controls, color))
             .no_stroke()
             .draw(canvas);
     }
 }
 
+/// Fill and/or stroke the flow-deformed rings around seed `p`; with
+/// AlongCurve coloring the palette runs from the outer ring inward.
+#[allow(clippy::too_many_arguments)]
+fn paint_rings(
+    controls: &Controls,
+    flow: &Field,
+    p: Point,
+    r: f32,
+    c: Color,
+    color_by: ColorBy,
+    colors: &[Color],
+    canvas: &mut Canvas,
+) {
+    let rc = &controls.ring_controls;
+    if !rc.fill && !rc.stroke {
+        return;
+    }
+    let sc = Color::from_rgba8(
+        rc.stroke_color.r(),
+        rc.stroke_color.g(),
+        rc.stroke_color.b(),
+        255,
+    );
+    let shapes = rc.shapes(flow, p, r);
+    let count = shapes.len();
+    for (k, outline) in shapes.iter().enumerate() {
+        let color = if color_by == ColorBy::AlongCurve {
+            let t = k as f32 / (count - 1).max(1) as f32;
+            sample_colors(colors, t, blend_space(controls))
+        } else {
+            c
+        };
+        let fill = ink(controls, color);
+        let line = ink(controls, if rc.fill { sc } else { color });
+        let mut sb = Shape::new().points(outline);
+        sb = if rc.fill {
+            sb.fill_paint(&fill)
+        } else {
+            sb.no_fill()
+        };
+        sb = if rc.stroke {
+            sb.stroke_paint(&line).stroke_weight(controls.stroke_width)
+        } else {
+            sb.no_stroke()
+        };
+        sb.draw(canvas);
+    }
+}
+
 #[allow(clippy::too_many_arguments)]
 fn paint_curve(
     controls: &Controls,
     len_fn: &(dyn Fn(Point) -> f32 + Send + Sync),
     pts: &[Point],
+    widths: Option<&[f32]>,
+    interval_at: Option<&dyn Fn(Point) -> f32>,
     c: Color,
     color_by: ColorBy,
     colors: &[Color],
     rng: &mut SmallRng,
+    tints: &mut Tints,
     canvas: &mut Canvas,
 ) {
+    // Per-point stroke widths when the field modulates them.
+    let width = |i: usize| widths.map_or(controls.stroke_width, |ws| ws[i]);
     // For AlongCurve, the color glides through the palette along the curve:
     // `cycles` sweeps per curve, optionally mirrored at the turnaround, with
     // an optional per-curve random phase; otherwise every point uses the
@@ -242,9 +514,11 @@ fn paint_curve(
         0.0
     };
     let denom = (pts.len() - 1).max(1) as f32;
-    let point_color = |i: usize| -> Color {
+    let space = blend_space(controls);
+    // `i` is a point index, fractional for points between the originals.
+    let color_at = |i: f32| -> Color {
         if color_by == ColorBy::AlongCurve {
-            let x = (i as f32 / denom) * cycles + phase;
+            let x = (i / denom) * cycles + phase;
             let t = if mirror {
                 let y = x.rem_euclid(2.0);
                 if y <= 1.0 {
@@ -259,11 +533,12 @@ fn paint_curve(
             } else {
                 x.rem_euclid(1.0)
             };
-            sample_colors(colors, t)
+            sample_colors(colors, t, space)
         } else {
             c
         }
     };
+    let point_color = |i: usize| color_at(i as f32);
 
     match controls
         .curve_style
@@ -276,15 +551,112 @@ fn paint_curve(
                 controls.dot_controls.dot_stroke_color.b(),
                 255,
             );
-            for (i, p) in pts.iter().enumerate() {
-                let r = len_fn(*p);
-                let mut sb = match controls
-                    .dot_controls
-                    .dot_style
-                    .expect("controls.dot_style cannot be None")
-                {
+            let dc = &controls.dot_controls;
+            let style = dc.dot_style.expect("controls.dot_style cannot be None");
+            let stroked = dc.stroke && controls.stroke_width >= 0.5;
+            let glyphs: Vec<char> = dc.glyphs.chars().collect();
+            let sprite_img = match (style, dc.sprite.as_deref()) {
+                (DotStyle::Sprite, Some(path)) => sprite(path),
+                _ => None,
+            };
+            let to_pixels = Transform::from_scale(canvas.scale, canvas.scale);
+            // Dots at every integration point, or evenly along the curve.
+            let (dots, at) = if dc.arc_spacing > 0.0 {
+                resample(pts, dc.arc_spacing)
+            } else {
+                (pts.to_vec(), (0..pts.len()).map(|i| i as f32).collect())
+            };
+            let every = dc.every.max(1) as usize;
+            for (k, (&q, &a)) in dots.iter().zip(&at).enumerate() {
+                if k % every != 0 || (dc.drop > 0.0 && rng.random::<f32>() < dc.drop) {
+                    continue;
+                }
+                // The point index of this dot, for per-point widths.
+                let i = (a.round() as usize).min(pts.len() - 1);
+                let r = len_fn(q);
+                // The curve direction here, estimated from the neighbors.
+                let angle = if dc.turns() {
+                    let prev = dots[k.saturating_sub(1)];
+                    let next = dots[(k + 1).min(dots.len() - 1)];
+                    (next.y - prev.y).atan2(next.x - prev.x)
+                } else {
+                    0.0
+                };
+                let p = if dc.jitter > 0.0 {
+                    let t = rng.random_range(0.0..TAU);
+                    let d = dc.jitter * rng.random::<f32>().sqrt();
+                    &pt(q.x + d * t.cos(), q.y + d * t.sin())
+                } else {
+                    &q
+                };
+                let color = color_at(a);
+                match style {
+                    DotStyle::Glyph => {
+                        if glyphs.is_empty() {
+                            break;
+                        }
+                        let Some(path) = glyph_path(glyphs[k % glyphs.len()], *p, r, angle)
+                        else {
+                            continue;
+                        };
+                        let mut paint = ink(controls, color);
+                        canvas
+                            .pixmap
+                            .fill_path(&path, &paint, FillRule::Winding, to_pixels, None);
+                        if stroked {
+                            paint.set_color(fade(sc, controls.opacity));
+                            let stroke = Stroke {
+                                width: width(i),
+                                ..Default::default()
+                            };
+                            canvas
+                                .pixmap
+                                .stroke_path(&path, &paint, &stroke, to_pixels, None);
+                        }
+                        continue;
+                    }
+                    DotStyle::Sprite => {
+                        let Some(img) = sprite_img.as_ref() else {
+                            break;
+                        };
+                        let Some(tile) = tints.get(img, color) else {
+                            continue;
+                        };
+                        // Fit the sprite's longer side to the dot diameter.
+                        let (w, h) = (img.width() as f32, img.height() as f32);
+                        let k = 2.0 * r / w.max(h);
+                        let transform = Transform::from_translate(-w / 2.0, -h / 2.0)
+                            .post_scale(k, k)
+                            .post_rotate(angle.to_degrees())
+                            .post_translate(p.x, p.y)
+                            .post_concat(to_pixels);
+                        canvas.pixmap.draw_pixmap(
+                            0,
+                            0,
+                            tile.as_ref(),
+                            &PixmapPaint {
+                                opacity: controls.opacity,
+                                blend_mode: blend_mode(controls),
+                                quality: FilterQuality::Bilinear,
+                                ..Default::default()
+                            },
+                            transform,
+                            None,
+                        );
+                        continue;
+                    }
+                    _ => {}
+                }
+                let (fill, line) = (ink(controls, color), ink(controls, sc));
+                let mut sb = match style {
                     DotStyle::Circle => Shape::new().circle(*p, r),
+                    DotStyle::Square if dc.orient => {
+                        Shape::new().points(&dc.vertices(style, *p, r, angle))
+                    }
                     DotStyle::Square => Shape::new().rect_cwh(*p, pt(2.0 * r, 2.0 * r)),
+                    DotStyle::Pearl if dc.orient => {
+                        Shape::new().points(&dc.pearl(*p, r, angle, rng))
+                    }
                     DotStyle::Pearl => Shape::new().pearl(
                         *p,
                         r,
@@ -293,54 +665,133 @@ fn paint_curve(
                         controls.dot_controls.pearl_smoothness,
                         rng,
                     ),
+                    _ => Shape::new().points(&dc.vertices(style, *p, r, angle)),
                 };
-                if !controls.dot_controls.stroke || controls.stroke_width < 0.5 {
+                if !stroked {
                     sb = sb.no_stroke();
                 } else {
-                    sb = sb
-                        .stroke_weight(controls.stroke_width)
-                        .stroke_color(fade(sc, controls.opacity))
+                    sb = sb.stroke_weight(width(i)).stroke_paint(&line)
                 }
-                sb.fill_color(fade(point_color(i), controls.opacity)).draw(canvas);
+                sb.fill_paint(&fill).draw(canvas);
             }
         }
         CurveStyle::Line => {
             let max_jump = (4.0 * controls.spacing).max(20.0);
-            if color_by == ColorBy::AlongCurve {
-                // Per-segment strokes so the color can glide along the line.
-                for i in 0..pts.len().saturating_sub(1) {
-                    if pts[i].dist2(pts[i + 1]) > max_jump * max_jump {
-                        continue;
-                    }
-                    Shape::new()
-                        .line(pts[i], pts[i + 1])
-                        .stroke_color(fade(point_color(i), controls.opacity))
-                        .stroke_weight(controls.stroke_width)
-                        .draw(canvas);
+            let stroke = &controls.stroke_controls;
+            let ribbon = stroke.mode.is_some_and(|m| m != StrokeMode::Plain);
+            // Ribbons can take their width from the size function instead,
+            // still scaled by any magnitude factor.
+            let base_width = |i: usize| {
+                if ribbon && stroke.follow_size {
+                    let m = widths
+                        .map_or(1.0, |ws| ws[i] / controls.stroke_width.max(f32::EPSILON));
+                    2.0 * len_fn(pts[i]) * m
+                } else {
+                    width(i)
                 }
-            } else {
-                // Split the polyline at any jump much larger than a step, so
-                // a discontinuity in the point list never draws a stray chord.
-                let mut seg_start = 0;
-                for i in 0..pts.len() {
-                    let broken = i + 1 == pts.len()
-                        || pts[i].dist2(pts[i + 1]) > max_jump * max_jump;
-                    if broken {
-                        if i > seg_start {
+            };
+            let width_at = |x: f32| {
+                let i = (x.floor() as usize).min(pts.len() - 1);
+                let j = (i + 1).min(pts.len() - 1);
+                let t = x - i as f32;
+                base_width(i) * (1.0 - t) + base_width(j) * t
+            };
+            // Per-segment strokes so the color and width can glide along the
+            // line.
+            let per_segment = color_by == ColorBy::AlongCurve || widths.is_some();
+            // Split the polyline at any jump much larger than a step, so a
+            // discontinuity in the point list never draws a stray chord; each
+            // unbroken run is simplified and smoothed on its own.
+            let mut seg_start = 0;
+            for i in 0..pts.len() {
+                let broken =
+                    i + 1 == pts.len() || pts[i].dist2(pts[i + 1]) > max_jump * max_jump;
+                if !broken {
+                    continue;
+                }
+                if i > seg_start {
+                    let (run, at) = smooth(
+                        &pts[seg_start..=i],
+                        stroke.smoothing.unwrap_or(Smoothing::Off),
+                        stroke.simplify,
+                        stroke.samples,
+                    );
+                    let at: Vec<f32> = at.iter().map(|x| x + seg_start as f32).collect();
+                    if ribbon {
+                        let outline = stroke.outline(&run, |j| width_at(at[j]));
+                        if color_by == ColorBy::AlongCurve {
+                            // One quad per segment so the color can glide;
+                            // the right edge runs backwards in the outline.
+                            let n = run.len();
+                            for j in 0..n - 1 {
+                                let quad = [
+                                    outline[j],
+                                    outline[j + 1],
+                                    outline[2 * n - 2 - j],
+                                    outline[2 * n - 1 - j],
+                                ];
+                                Shape::new()
+                                    .points(&quad)
+                                    .fill_paint(&ink(controls, color_at(at[j])))
+                                    .no_stroke()
+                                    .draw(canvas);
+                            }
+                        } else {
+                            Shape::new()
+                                .points(&outline)
+                                .fill_paint(&ink(controls, c))
+                                .no_stroke()
+                                .draw(canvas);
+                        }
+                    } else if per_segment {
+                        for j in 0..run.len() - 1 {
                             Shape::new()
-                                .points(&pts[seg_start..=i])
-                                .no_fill()
-                                .stroke_color(fade(c, controls.opacity))
-                                .stroke_weight(controls.stroke_width)
+                                .line(run[j], run[j + 1])
+                                .stroke_paint(&ink(controls, color_at(at[j])))
+                                .stroke_weight(width_at(at[j]))
                                 .draw(canvas);
                         }
-                        seg_start = i + 1;
+                    } else {
+                        Shape::new()
+                            .points(&run)
+                            .no_fill()
+                            .stroke_paint(&ink(controls, c))
+                            .stroke_weight(controls.stroke_width)
+                            .draw(canvas);
                     }
                 }
+                seg_start = i + 1;
+            }
+        }
+        CurveStyle::Brush => controls.brush_controls.paint(
+            pts,
+            (4.0 * controls.spacing).max(20.0),
+            |i| len_fn(pts[i]),
+            &point_color,
+            controls.opacity,
+            blend_mode(controls),
+            rng,
+            canvas,
+        ),
+        CurveStyle::Hatch => {
+            let hatch = &controls.hatch_controls;
+            let fixed = |_: Point| hatch.interval;
+            let strokes = hatch.strokes(
+                pts,
+                (4.0 * controls.spacing).max(20.0),
+                interval_at.unwrap_or(&fixed),
+            );
+            for (a, b, i) in strokes {
+                Shape::new()
+                    .line(a, b)
+                    .stroke_paint(&ink(controls, point_color(i)))
+                    .stroke_weight(width(i))
+                    .draw(canvas);
             }
         }
-        // Strips are painted per neighbor pair in paint_strip.
-        CurveStyle::Strips => {}
+        // Strips are painted per neighbor pair in paint_strip, and rings
+        // around their seeds in paint_rings; isolines have no curves.
+        CurveStyle::Strips | CurveStyle::Rings | CurveStyle::Isolines => {}
         CurveStyle::Extrusion => {
             let extrude_dir = controls
                 .extrude_controls
@@ -369,7 +820,7 @@ fn paint_curve(
                 };
                 let (x0, y0) = (p.x - dx, p.y - dy);
                 let (x1, y1) = (p.x + dx, p.y + dy);
-                let lg = paint_lg(
+                let mut lg = paint_lg(
                     x0,
                     y0,
                     x1,
@@ -382,9 +833,10 @@ fn paint_curve(
                     controls.opacity,
                     rng,
                 );
+                lg.blend_mode = blend_mode(controls);
                 Shape::new()
                     .line(pt(x0, y0), pt(x1, y1))
-                    .stroke_weight(controls.stroke_width)
+                    .stroke_weight(width(i))
                     .stroke_paint(&lg)
                     .draw(canvas);
             }
@@ -392,6 +844,131 @@ fn paint_curve(
     }
 }
 
+/// The color space the layer's palette is blended in.
+fn blend_space(controls: &Controls) -> Interpolation {
+    controls
+        .color_mode_controls
+        .interpolation
+        .unwrap_or(Interpolation::Srgb)
+}
+
+/// The palette the curves are colored from.
+fn curve_colors(controls: &Controls) -> Vec<Color> {
+    match controls
+        .color_mode_controls
+        .mode
+        .expect("controls.mode cannot be None")
+    {
+        ColorMode::Scale => color_scale(
+            Color::from_rgba8(
+                controls.color_mode_controls.anchor1.r(),
+                controls.color_mode_controls.anchor1.g(),
+                controls.color_mode_controls.anchor1.b(),
+                255,
+            ),
+            Color::from_rgba8(
+                controls.color_mode_controls.anchor2.r(),
+                controls.color_mode_controls.anchor2.g(),
+                controls.color_mode_controls.anchor2.b(),
+                255,
+            ),
+            8,
+            controls
+                .color_mode_controls
+                .scale_interpolation
+                .unwrap_or(Interpolation::Okhsl),
+        ),
+        ColorMode::Palette => palette_colors(
+            controls.color_mode_controls.palette_choice.unwrap(),
+            blend_space(controls),
+        ),
+        ColorMode::Gradient => {
+            gradient_colors(&controls.color_mode_controls.stops, blend_space(controls))
+        }
+        ColorMode::Harmony => controls.color_mode_controls.harmony.colors(),
+        // An empty user palette (none chosen yet) falls back to the named one.
+        ColorMode::User => match &controls.color_mode_controls.user_palette.colors {
+            colors if colors.is_empty() => palette_colors(
+                controls.color_mode_controls.palette_choice.unwrap(),
+                blend_space(controls),
+            ),
+            colors => colors.iter().map(|c| to_color(*c)).collect(),
+        },
+    }
+}
+
+/// Draw the noise as a contour map: `levels` isolines spread evenly over its
+/// range, optionally with the bands between them filled from the palette.
+fn paint_isolines(controls: &Controls, colors: &[Color], canvas: &mut Canvas) {
+    let ic = &controls.isoline_controls;
+    let space = blend_space(controls);
+    let flow = choose_flow(controls, canvas.width(), canvas.height());
+    let grid = ScalarGrid::sample(canvas.w_f32(), canvas.h_f32(), ic.resolution, |x, y| {
+        noise2d(&flow.noise_function, &flow.noise_opts, x, y)
+    });
+    let (lo, hi) = grid.range();
+    let levels = ic.levels.max(1) as usize;
+    let step = (hi - lo) / (levels + 1) as f32;
+    if step <= 0.0 {
+        return;
+    }
+    if ic.fill {
+        // Filled per pixel from the interpolated grid, so the band edges
+        // stay crisp at any output scale. Band k lies between levels k - 1
+        // and k.
+        let (pw, scale) = (canvas.pixmap.width(), canvas.scale);
+        let mut layer = Pixmap::new(pw, canvas.pixmap.height()).unwrap();
+        layer
+            .pixels_mut()
+            .par_chunks_mut(pw as usize)
+            .enumerate()
+            .for_each(|(j, row)| {
+                let y = (j as f32 + 0.5) / scale;
+                for (i, px) in row.iter_mut().enumerate() {
+                    let v = grid.value((i as f32 + 0.5) / scale, y);
+                    let band = (((v - lo) / step) as usize).min(levels);
+                    let c = sample_colors(colors, band as f32 / levels as f32, space);
+                    *px = fade(c, controls.opacity).premultiply().to_color_u8();
+                }
+            });
+        canvas.pixmap.draw_pixmap(
+            0,
+            0,
+            layer.as_ref(),
+            &PixmapPaint {
+                blend_mode: blend_mode(controls),
+                ..Default::default()
+            },
+            Transform::identity(),
+            None,
+        );
+    }
+    if !ic.lines {
+        return;
+    }
+    let lc = Color::from_rgba8(ic.line_color.r(), ic.line_color.g(), ic.line_color.b(), 255);
+    for k in 0..levels {
+        let color = if ic.fill {
+            lc
+        } else {
+            sample_colors(colors, k as f32 / (levels - 1).max(1) as f32, space)
+        };
+        for line in grid.contours(lo + step * (k + 1) as f32) {
+            let line = if ic.smooth {
+                smooth(&line, Smoothing::CatmullRom, 0.0, 4).0
+            } else {
+                line
+            };
+            Shape::new()
+                .points(&line)
+                .no_fill()
+                .stroke_paint(&ink(controls, color))
+                .stroke_weight(controls.stroke_width)
+                .draw(canvas);
+        }
+    }
+}
+
 /// Render the artwork. `scale` multiplies the logical canvas size: 1.0 for
 /// the display image, below 1.0 for fast previews, above 1.0 for print.
 pub fn draw(controls: &Controls, scale: f32) -> Canvas {
@@ -432,6 +1009,7 @@ pub fn draw(controls: &Controls, scale: f32) -> Canvas {
         ),
         Background::White => BG::solid(canvas.width(), canvas.height(), *WHITE),
         Background::Black => BG::solid(canvas.width(), canvas.height(), *BLACK),
+        Background::Transparent => BG::solid(canvas.width(), canvas.height(), Color::TRANSPARENT),
         Background::Solid => BG::solid(
             canvas.width(),
             canvas.height(),
@@ -442,9 +1020,106 @@ pub fn draw(controls: &Controls, scale: f32) -> Canvas {
                 255,
             ),
         ),
+        kind @ (Background::Linear | Background::Radial | Background::Conic) => BG::gradient(
+            pw,
+            ph,
+            kind,
+            &curve_colors(controls),
+            blend_space(controls),
+            controls.background_controls.angle,
+        ),
+        Background::Wash => BG::wash(
+            pw,
+            ph,
+            ps,
+            &curve_colors(controls),
+            controls.background_controls.wash_size,
+        ),
+        Background::Paper => BG::paper(
+            pw,
+            ph,
+            ps,
+            controls.background_controls.paper.as_deref(),
+            controls.background_controls.paper_scale,
+        ),
+        Background::Field => {
+            let flow = choose_flow(controls, canvas.width(), canvas.height());
+            let mut grid = ScalarGrid::sample(canvas.w_f32(), canvas.h_f32(), 4.0, |x, y| {
+                noise2d(&flow.noise_function, &flow.noise_opts, x, y)
+            });
+            grid.blur(controls.background_controls.softness);
+            BG::field(pw, ph, ps, &grid, &curve_colors(controls), blend_space(controls))
+        }
+        Background::Image => {
+            let bc = &controls.background_controls;
+            let (path, rotation) = if bc.use_noise_image {
+                (controls.image_noise.path.as_deref(), controls.image_noise.rotation)
+            } else {
+                (bc.image.as_deref(), bc.rotation)
+            };
+            // Drawn, not sampled, so at full resolution for large exports.
+            let img = path.and_then(|p| {
+                crate::imgnoise::full_image(
+                    p,
+                    rotation.unwrap_or(crate::imgnoise::Rotation::Deg0),
+                )
+            });
+            BG::image(pw, ph, ps, img.as_deref(), bc)
+        }
     };
     bg.canvas_bg(&mut canvas);
 
+    // The layers, bottom to top. The base layer carries on with the
+    // background's rng; the others have their own seeds.
+    for (k, layer) in std::iter::once(controls).chain(&controls.layers).enumerate() {
+        if !layer.layer.visible {
+            continue;
+        }
+        let mut seeded;
+        let rng = if k == 0 {
+            &mut rng
+        } else {
+            seeded = SmallRng::seed_from_u64(match layer.layer.seed {
+                0 => SEED.wrapping_add(k as u64),
+                seed => seed,
+            });
+            &mut seeded
+        };
+        // Plain layers draw straight onto the canvas; a faded or blended one
+        // is drawn on its own and composited as a whole.
+        let opacity = layer.layer.opacity.clamp(0.0, 1.0);
+        let blend = layer.layer.blend.unwrap_or(Blend::Normal);
+        if opacity >= 1.0 && blend == Blend::Normal {
+            draw_layer(layer, rng, &mut canvas);
+        } else {
+            let mut over = Canvas::with_scale(canvas.width(), canvas.height(), canvas.scale);
+            draw_layer(layer, rng, &mut over);
+            canvas.pixmap.draw_pixmap(
+                0,
+                0,
+                over.pixmap.as_ref(),
+                &PixmapPaint {
+                    opacity,
+                    blend_mode: blend.mode(),
+                    ..Default::default()
+                },
+                Transform::identity(),
+                None,
+            );
+        }
+    }
+    controls.effects.apply(&mut canvas, rng.next_u64());
+    canvas
+}
+
+/// Draw one layer's curves over `canvas`.
+fn draw_layer(controls: &Controls, rng: &mut SmallRng, canvas: &mut Canvas) {
+    // Isolines trace the noise itself, so there are no seeds or curves.
+    if controls.curve_style == Some(CurveStyle::Isolines) {
+        paint_isolines(controls, &curve_colors(controls), canvas);
+        return;
+    }
+
     // The Field is rebuilt per render chunk below: noise 0.9's Worley holds an
     // Rc internally, so a single Field cannot be shared across threads.
 
@@ -461,15 +1136,28 @@ pub fn draw(controls: &Controls, scale: f32) -> Canvas {
             sep,
             controls.column_angle,
             controls.line_shift,
-            &mut rng,
+            rng,
         );
 
     // Curves are pre-generated when seeding and growth are coupled (evenly
-    // spaced streamlines) or when strips need the whole ordered set to pair
-    // neighbors. Otherwise they are generated inside the render chunks.
+    // spaced streamlines, colliding curves) or when strips need the whole
+    // ordered set to pair neighbors. Otherwise they are generated inside the
+    // render chunks.
     let pregen: Option<Vec<(Point, Vec<Point>)>> = if even {
         let flow = choose_flow(controls, canvas.width(), canvas.height());
-        Some(flow.evenly_spaced(sep, two_sided))
+        let sep_at = density_map(
+            controls,
+            &controls.density_map,
+            &flow,
+            canvas.w_f32(),
+            canvas.h_f32(),
+            sep,
+        );
+        Some(flow.evenly_spaced(sep, sep_at.as_ref(), two_sided))
+    } else if controls.stops.collide && !strips {
+        // Colliding curves each depend on all the curves before them.
+        let flow = choose_flow(controls, canvas.width(), canvas.height());
+        Some(flow.curves_avoiding(&starts, controls.stops.gap * sep, two_sided))
     } else if strips {
         // End extension would misalign the point indices of neighboring
         // curves, so strips always use plain curves.
@@ -489,28 +1177,7 @@ pub fn draw(controls: &Controls, scale: f32) -> Canvas {
         None => starts,
     };
 
-    let colors: Vec<Color> = match controls
-        .color_mode_controls
-        .mode
-        .expect("controls.mode cannot be None")
-    {
-        ColorMode::Scale => color_scale(
-            Color::from_rgba8(
-                controls.color_mode_controls.anchor1.r(),
-                controls.color_mode_controls.anchor1.g(),
-                controls.color_mode_controls.anchor1.b(),
-                255,
-            ),
-            Color::from_rgba8(
-                controls.color_mode_controls.anchor2.r(),
-                controls.color_mode_controls.anchor2.g(),
-                controls.color_mode_controls.anchor2.b(),
-                255,
-            ),
-            8,
-        ),
-        ColorMode::Palette => palette_colors(controls.color_mode_controls.palette_choice.unwrap()),
-    };
+    let colors = curve_colors(controls);
     let mut palette = Palette::new(colors.clone());
     let color_by = controls
         .color_mode_controls
@@ -526,7 +1193,23 @@ pub fn draw(controls: &Controls, scale: f32) -> Canvas {
     let region_colors = controls.color_mode_controls.region_colors.clamp(2, 12);
     let (cx, cy) = (canvas.w_f32() / 2.0, canvas.h_f32() / 2.0);
 
-    let len_fn = if controls.curve_style == Some(CurveStyle::Dots) {
+    // Line ribbons, the Brush and Rings each have their own size function.
+    let own_size = match controls.curve_style {
+        Some(CurveStyle::Line) => Some(&controls.stroke_controls.size_controls),
+        Some(CurveStyle::Brush) => Some(&controls.brush_controls.size_controls),
+        Some(CurveStyle::Rings) => Some(&controls.ring_controls.size_controls),
+        _ => None,
+    };
+    let len_fn = if let Some(size) = own_size {
+        size.size_fn.expect("controls.size_fn cannot be None").calc(
+            canvas.w_f32(),
+            canvas.h_f32(),
+            size.size,
+            size.direction.expect("controls.direction cannot be None"),
+            size.size_scale,
+            size.min_size,
+        )
+    } else if controls.curve_style == Some(CurveStyle::Dots) {
         controls.dot_controls.size_controls.size_fn.unwrap().calc(
             canvas.w_f32(),
             canvas.h_f32(),
@@ -580,6 +1263,7 @@ pub fn draw(controls: &Controls, scale: f32) -> Canvas {
     } else {
         Vec::new()
     };
+    let space = blend_space(controls);
     let jobs: Vec<(usize, Point, Color, u64)> = seeds
         .iter()
         .copied()
@@ -589,28 +1273,32 @@ pub fn draw(controls: &Controls, scale: f32) -> Canvas {
                 ColorBy::Random => palette.rand_color(),
                 ColorBy::Cycle => colors[i % colors.len()],
                 ColorBy::Order => {
-                    sample_colors(&colors, i as f32 / (n_curves - 1).max(1) as f32)
+                    sample_colors(&colors, i as f32 / (n_curves - 1).max(1) as f32, space)
+                }
+                ColorBy::PositionX => {
+                    sample_colors(&colors, direction(p.x / canvas.w_f32()), space)
+                }
+                ColorBy::PositionY => {
+                    sample_colors(&colors, direction(p.y / canvas.h_f32()), space)
                 }
-                ColorBy::PositionX => sample_colors(&colors, direction(p.x / canvas.w_f32())),
-                ColorBy::PositionY => sample_colors(&colors, direction(p.y / canvas.h_f32())),
                 ColorBy::Radial => {
                     let d = ((p.x - cx).powi(2) + (p.y - cy).powi(2)).sqrt();
                     // Points at the same distance share a rank, so rings of
                     // starts stay a single color.
                     let rank = radial_sorted.partition_point(|x| *x < d);
                     let t = rank as f32 / (radial_sorted.len() - 1).max(1) as f32;
-                    sample_colors(&colors, direction(t))
+                    sample_colors(&colors, direction(t), space)
                 }
                 ColorBy::FlowAngle => {
-                    let f = color_field.as_ref().unwrap();
-                    let a = noise2d(&f.noise_function, &f.noise_opts, p.x, p.y) * PI;
-                    sample_colors(&colors, a.rem_euclid(TAU) / TAU)
+                    let a = color_field.as_ref().unwrap().flow_angle(p.x, p.y);
+                    sample_colors(&colors, a.rem_euclid(TAU) / TAU, space)
                 }
                 ColorBy::NoiseValue => {
                     let f = color_field.as_ref().unwrap();
                     sample_colors(
                         &colors,
                         noise2d_01(&f.noise_function, &f.noise_opts, p.x, p.y),
+                        space,
                     )
                 }
                 ColorBy::Region => {
@@ -619,7 +1307,7 @@ pub fn draw(controls: &Controls, scale: f32) -> Canvas {
                     // the palette.
                     let t = noise2d_01(&region_noise, &region_opts, p.x, p.y);
                     let bin = ((t * region_colors as f32) as u32).min(region_colors - 1);
-                    sample_colors(&colors, bin as f32 / (region_colors - 1) as f32)
+                    sample_colors(&colors, bin as f32 / (region_colors - 1) as f32, space)
                 }
                 // Per-point colors are sampled in render_curve.
                 ColorBy::AlongCurve => colors[0],
@@ -627,40 +1315,71 @@ pub fn draw(controls: &Controls, scale: f32) -> Canvas {
             (i, p, c, rng.next_u64())
         })
         .collect();
-    // Strips pair each curve with the next one from the ordered start list;
-    // pairs whose seeds are far apart (grid column wraps, scattered
-    // generators) are skipped.
+    // Strips pair each curve with a neighbor: the next one along a seed
+    // line, whose curves grow in step, or else the nearest later seed, with
+    // each point matched to the closest point of the neighbor. Pairs whose
+    // seeds are far apart (grid column wraps, scattered generators) are
+    // skipped.
     let pregen_curves: Option<Vec<Vec<Point>>> =
         pregen.map(|v| v.into_iter().map(|x| x.1).collect());
+    let in_step = controls.location == Some(crate::location::Location::Line);
+    let max_pair = 3.0 * sep.max(5.0);
+    let neighbors = if strips {
+        strip_neighbors(&seeds, in_step, max_pair)
+    } else {
+        Vec::new()
+    };
     let jobs: Vec<(usize, Point, Color, u64)> = if strips {
-        let max_pair = (3.0 * sep.max(5.0)).powi(2);
         jobs.into_iter()
-            .filter(|(i, p, _, _)| {
-                *i + 1 < seeds.len() && p.dist2(seeds[*i + 1]) < max_pair
-            })
+            .filter(|(i, _, _, _)| neighbors[*i].is_some())
             .collect()
     } else {
         jobs
     };
-    let chunk_size = jobs
-        .len()
-        .div_ceil(rayon::current_num_threads())
-        .max(1);
+    // A fixed number of chunks, not one per thread: relief is lit per
+    // chunk, so the split must not depend on the machine.
+    let chunk_size = jobs.len().div_ceil(RENDER_CHUNKS).max(1);
     let layers: Vec<Pixmap> = jobs
         .par_chunks(chunk_size)
         .map(|chunk| {
             let mut layer = Canvas::with_scale(canvas.width(), canvas.height(), canvas.scale);
-            let flow = pregen_curves
-                .is_none()
+            let modulate_width = controls.magnitude.width > 0.0;
+            let hatch = controls.curve_style == Some(CurveStyle::Hatch);
+            let rings = controls.curve_style == Some(CurveStyle::Rings);
+            let flow = (pregen_curves.is_none() || modulate_width || hatch || rings)
                 .then(|| choose_flow(controls, canvas.width(), canvas.height()));
+            // Hatch strokes crowd together where the hatch density map is strong.
+            let interval_at = flow.as_ref().filter(|_| hatch).map(|f| {
+                density_map(
+                    controls,
+                    &controls.hatch_controls.density,
+                    f,
+                    canvas.w_f32(),
+                    canvas.h_f32(),
+                    controls.hatch_controls.interval,
+                )
+            });
+            let mut tints = Tints::default();
             for (i, p, c, seed) in chunk {
                 let mut rng = SmallRng::seed_from_u64(*seed);
-                if strips {
+                if rings {
+                    paint_rings(
+                        controls,
+                        flow.as_ref().unwrap(),
+                        *p,
+                        len_fn(*p),
+                        *c,
+                        color_by,
+                        &colors,
+                        &mut layer,
+                    );
+                } else if strips {
                     let curves = pregen_curves.as_ref().unwrap();
+                    let b = &curves[neighbors[*i].unwrap()];
                     paint_strip(
                         controls,
                         &curves[*i],
-                        &curves[*i + 1],
+                        &strip_partners(&curves[*i], b, in_step, max_pair),
                         *c,
                         color_by,
                         &colors,
@@ -675,18 +1394,32 @@ pub fn draw(controls: &Controls, scale: f32) -> Canvas {
                             &generated
                         }
                     };
+                    // A seed ruled out by a termination rule has no curve.
+                    if pts.is_empty() {
+                        continue;
+                    }
+                    let widths: Option<Vec<f32>> = modulate_width.then(|| {
+                        let f = flow.as_ref().unwrap();
+                        pts.iter()
+                            .map(|q| controls.stroke_width * f.width_factor(q.x, q.y))
+                            .collect()
+                    });
                     paint_curve(
                         controls,
                         len_fn.as_ref(),
                         pts,
+                        widths.as_deref(),
+                        interval_at.as_deref(),
                         *c,
                         color_by,
                         &colors,
                         &mut rng,
+                        &mut tints,
                         &mut layer,
                     );
                 }
             }
+            controls.relief.apply(&mut layer.pixmap, canvas.scale);
             layer.pixmap
         })
         .collect();
@@ -695,10 +1428,12 @@ pub fn draw(controls: &Controls, scale: f32) -> Canvas {
             0,
             0,
             layer.as_ref(),
-            &PixmapPaint::default(),
+            &PixmapPaint {
+                blend_mode: blend_mode(controls),
+                ..Default::default()
+            },
             Transform::identity(),
             None,
         );
     }
-    canvas
 }
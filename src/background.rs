@@ -1,3 +1,9 @@
+use crate::color::{sample_colors, Interpolation};
+use crate::gui::{color_picker, pick_list, section, SliderRow};
+use crate::imgnoise::Rotation;
+use crate::isoline::ScalarGrid;
+use eframe::egui;
+use image::RgbaImage;
 use rand::RngCore;
 use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
@@ -13,6 +19,22 @@ pub enum Background {
     White,
     Black,
     Solid,
+    /// A straight gradient through the palette.
+    Linear,
+    /// A gradient out from the center.
+    Radial,
+    /// A gradient around the center.
+    Conic,
+    /// Watercolor washes of the palette.
+    Wash,
+    /// A tileable paper image.
+    Paper,
+    /// The flow field's noise as a soft color map.
+    Field,
+    /// A photograph or any other image, to draw the curves over.
+    Image,
+    /// No background; exports keep the curves' alpha.
+    Transparent,
 }
 
 impl std::fmt::Display for Background {
@@ -29,17 +51,259 @@ impl std::fmt::Display for Background {
                 Background::White => "Solid White",
                 Background::Black => "Solid Black",
                 Background::Solid => "Solid Color",
+                Background::Linear => "Linear Gradient",
+                Background::Radial => "Radial Gradient",
+                Background::Conic => "Conic Gradient",
+                Background::Wash => "Watercolor",
+                Background::Paper => "Paper Image",
+                Background::Field => "Blurred Field",
+                Background::Image => "Image",
+                Background::Transparent => "Transparent",
             }
         )
     }
 }
+
+/// How an image background fills a canvas of another shape.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ImageFit {
+    /// Fill the canvas, cropping the image.
+    Cover,
+    /// Show the whole image, on white where it falls short.
+    Fit,
+}
+
+impl std::fmt::Display for ImageFit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                ImageFit::Cover => "Cover",
+                ImageFit::Fit => "Fit",
+            }
+        )
+    }
+}
+
+/// Settings for the gradient, wash, paper, field and image backgrounds; the
+/// gradients, wash and field take their colors from the base layer's palette.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BackgroundControls {
+    /// Linear and Conic: direction in degrees, 0 runs left to right.
+    pub angle: f32,
+    /// Wash: size of the blooms.
+    pub wash_size: f32,
+    /// Paper: the tileable image.
+    pub paper: Option<String>,
+    /// Paper: tile size relative to the image.
+    pub paper_scale: f32,
+    /// Field: blur radius of the color map in pixels.
+    pub softness: f32,
+    /// Image: the picture, unless the flow field's image is used.
+    #[serde(default)]
+    pub image: Option<String>,
+    /// Image: use the image noise file and rotation instead of `image`.
+    #[serde(default)]
+    pub use_noise_image: bool,
+    #[serde(default = "default_rotation")]
+    pub rotation: Option<Rotation>,
+    #[serde(default = "default_fit")]
+    pub fit: Option<ImageFit>,
+    /// Image: gaussian blur sigma in pixels.
+    #[serde(default)]
+    pub image_blur: f32,
+    /// Image: 0 keeps the colors, 1 is grayscale.
+    #[serde(default)]
+    pub desaturate: f32,
+    /// Image: a color multiplied in by `tint_amount`.
+    #[serde(default = "default_tint")]
+    pub tint: egui::Color32,
+    #[serde(default)]
+    pub tint_amount: f32,
+}
+
+fn default_rotation() -> Option<Rotation> {
+    Some(Rotation::Deg0)
+}
+
+fn default_fit() -> Option<ImageFit> {
+    Some(ImageFit::Cover)
+}
+
+fn default_tint() -> egui::Color32 {
+    egui::Color32::from_rgb(112, 66, 20)
+}
+
+impl Default for BackgroundControls {
+    fn default() -> Self {
+        Self {
+            angle: 90.0,
+            wash_size: 1.0,
+            paper: None,
+            paper_scale: 1.0,
+            softness: 20.0,
+            image: None,
+            use_noise_image: false,
+            rotation: Some(Rotation::Deg0),
+            fit: Some(ImageFit::Cover),
+            image_blur: 0.0,
+            desaturate: 0.0,
+            tint: default_tint(),
+            tint_amount: 0.0,
+        }
+    }
+}
+
+/// The file dialog filter for background images.
+const IMAGES: (&str, &[&str]) = ("image", &["png", "jpg", "jpeg"]);
+
+/// A button showing the file name of `path` that picks a new image of one
+/// of the `filter` extensions.
+pub fn image_button(
+    ui: &mut egui::Ui,
+    path: &mut Option<String>,
+    hover: [&str; 2],
+    filter: (&str, &[&str]),
+) {
+    let name = path
+        .as_deref()
+        .and_then(|p| std::path::Path::new(p).file_name())
+        .map_or("Choose...".to_string(), |n| n.to_string_lossy().to_string());
+    let button = ui.add(egui::Button::new(name).min_size(egui::vec2(150.0, 0.0)));
+    let button = match path.as_deref() {
+        Some(p) => button.on_hover_text(p),
+        None => button.on_hover_ui(|ui| {
+            ui.colored_label(egui::Color32::ORANGE, hover[0]);
+            ui.colored_label(egui::Color32::ORANGE, hover[1]);
+        }),
+    };
+    if button.clicked() {
+        if let Some(picked) = rfd::FileDialog::new()
+            .add_filter(filter.0, filter.1)
+            .pick_file()
+        {
+            *path = Some(picked.to_string_lossy().to_string());
+        }
+    }
+}
+
+impl BackgroundControls {
+    pub fn ui(&mut self, ui: &mut egui::Ui, background: Background) {
+        section(ui, "Background");
+        egui::Grid::new("background")
+            .spacing((15.0, 10.0))
+            .min_col_width(90.0)
+            .show(ui, |ui| match background {
+                Background::Linear | Background::Conic => {
+                    SliderRow::new("Angle", &mut self.angle, 90.0, 0.0..=360.0)
+                        .hover(&["Direction of the gradient;", "0 runs left to right."])
+                        .steps(5.0, 15.0)
+                        .show(ui);
+                }
+                Background::Wash => {
+                    SliderRow::new("Size", &mut self.wash_size, 1.0, 0.1..=5.0)
+                        .hover(&["Size of the washes."])
+                        .steps(0.1, 0.5)
+                        .decimals(1)
+                        .show(ui);
+                }
+                Background::Paper => {
+                    ui.label("Image");
+                    image_button(
+                        ui,
+                        &mut self.paper,
+                        ["Click to select a tileable", "paper texture."],
+                        IMAGES,
+                    );
+                    ui.end_row();
+                    SliderRow::new("Tile Scale", &mut self.paper_scale, 1.0, 0.1..=4.0)
+                        .hover(&["Size of each tile relative", "to the image."])
+                        .steps(0.05, 0.25)
+                        .decimals(2)
+                        .show(ui);
+                }
+                Background::Field => {
+                    SliderRow::new("Softness", &mut self.softness, 20.0, 0.0..=100.0)
+                        .hover(&["Blur of the field's", "color map in pixels."])
+                        .steps(1.0, 5.0)
+                        .show(ui);
+                }
+                Background::Image => {
+                    ui.label("Noise Image").on_hover_ui(|ui| {
+                        ui.colored_label(egui::Color32::ORANGE, "Use the image and rotation");
+                        ui.colored_label(egui::Color32::ORANGE, "of the image flow field.");
+                    });
+                    ui.checkbox(&mut self.use_noise_image, "");
+                    ui.end_row();
+                    if !self.use_noise_image {
+                        ui.label("Image");
+                        image_button(
+                            ui,
+                            &mut self.image,
+                            ["Click to select the image", "to draw over."],
+                            IMAGES,
+                        );
+                        ui.end_row();
+                        pick_list(
+                            ui,
+                            "Rotation",
+                            &[
+                                Rotation::Deg0,
+                                Rotation::Deg90,
+                                Rotation::Deg180,
+                                Rotation::Deg270,
+                            ],
+                            &mut self.rotation,
+                        );
+                    }
+                    pick_list(ui, "Fit", &[ImageFit::Cover, ImageFit::Fit], &mut self.fit);
+                    SliderRow::new("Blur", &mut self.image_blur, 0.0, 0.0..=50.0)
+                        .hover(&["Gaussian blur of the", "image in pixels."])
+                        .steps(0.5, 2.0)
+                        .decimals(1)
+                        .show(ui);
+                    SliderRow::new("Desaturate", &mut self.desaturate, 0.0, 0.0..=1.0)
+                        .steps(0.05, 0.1)
+                        .decimals(2)
+                        .show(ui);
+                    color_picker(ui, "Tint", &mut self.tint);
+                    SliderRow::new("Tint Amount", &mut self.tint_amount, 0.0, 0.0..=1.0)
+                        .hover(&["How strongly the tint is", "multiplied into the image."])
+                        .steps(0.05, 0.1)
+                        .decimals(2)
+                        .show(ui);
+                }
+                _ => {}
+            });
+    }
+}
+
+/// The paper tile at `path` as a premultiplied pixmap, from the shared
+/// cache of decoded images.
+fn paper_tile(path: &str) -> Option<Pixmap> {
+    let img = crate::imgnoise::original(path, crate::imgnoise::NOISE_SIDE)?;
+    let mut tile = Pixmap::new(img.width(), img.height())?;
+    for (px, src) in tile.pixels_mut().iter_mut().zip(img.pixels()) {
+        *px = Color::from_rgba8(src[0], src[1], src[2], src[3])
+            .premultiply()
+            .to_color_u8();
+    }
+    Some(tile)
+}
+
+fn smooth_step(lo: f32, hi: f32, x: f32) -> f32 {
+    let t = ((x - lo) / (hi - lo)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
 pub struct BG(Canvas);
 
 /// A zero-mean, unit-variance film grain field: gaussian noise softly
 /// blurred with a radius proportional to the render scale, so the grain
 /// keeps the same size relative to the image at preview, display, and
 /// print resolutions.
-fn film_grain(width: u32, height: u32, scale: f32, size: f32, seed: u64) -> Vec<f32> {
+pub fn film_grain(width: u32, height: u32, scale: f32, size: f32, seed: u64) -> Vec<f32> {
     let w = width as usize;
     let h = height as usize;
     let mut noise = vec![0.0f32; w * h];
@@ -226,6 +490,184 @@ impl BG {
         })
     }
 
+    /// A Linear, Radial or Conic gradient through `colors`, blended in
+    /// `space`; `angle` turns the linear direction and the conic start, in
+    /// degrees.
+    pub fn gradient(
+        width: u32,
+        height: u32,
+        kind: Background,
+        colors: &[Color],
+        space: Interpolation,
+        angle: f32,
+    ) -> Self {
+        let (cx, cy) = (width as f32 / 2.0, height as f32 / 2.0);
+        let a = angle.to_radians();
+        let (sin, cos) = a.sin_cos();
+        // Half the canvas extent along the gradient direction.
+        let reach = (cx * cos.abs() + cy * sin.abs()).max(1.0);
+        let r_max = (cx * cx + cy * cy).sqrt().max(1.0);
+        Self::from_pixels(width, height, 0, |i, j, _| {
+            let (x, y) = (i as f32 + 0.5 - cx, j as f32 + 0.5 - cy);
+            let t = match kind {
+                Background::Radial => (x * x + y * y).sqrt() / r_max,
+                Background::Conic => {
+                    // Out and back around the circle, so there is no seam.
+                    let u = (y.atan2(x) - a).rem_euclid(TAU) / TAU;
+                    1.0 - (2.0 * u - 1.0).abs()
+                }
+                _ => 0.5 + 0.5 * (x * cos + y * sin) / reach,
+            };
+            sample_colors(colors, t, space)
+        })
+    }
+
+    /// Watercolor washes: soft blooms of up to four palette colors over
+    /// white paper, glazed over one another, with darker rims where the
+    /// pigment dried and a little granulation.
+    pub fn wash(width: u32, height: u32, scale: f32, colors: &[Color], size: f32) -> Self {
+        let nf = Turbulence::<_, Perlin>::new(Fbm::<Perlin>::default().set_octaves(5))
+            .set_power(0.4)
+            .set_roughness(4);
+        let opts = NoiseOpts::default();
+        let pigments: Vec<Color> = match colors.len() {
+            0 => Vec::new(),
+            n => (0..n.min(4))
+                .map(|k| colors[k * (n - 1) / n.min(4).saturating_sub(1).max(1)])
+                .collect(),
+        };
+        let f = 0.004 / size.max(0.1);
+        Self::from_pixels(width, height, 0, |i, j, _| {
+            let (x, y) = (i as f32 / scale, j as f32 / scale);
+            let grain = noise2d_01(&nf, &opts, x * 0.25, y * 0.25);
+            let mut c = [0.97f32, 0.96, 0.93];
+            for (k, p) in pigments.iter().enumerate() {
+                let o = 37.0 * (k + 1) as f32;
+                let n = noise2d_01(&nf, &opts, x * f + o, y * f - o);
+                let body = smooth_step(0.5, 0.6, n);
+                let rim = smooth_step(0.5, 0.53, n) * (1.0 - smooth_step(0.53, 0.62, n));
+                let d = (0.35 * body + 0.25 * rim) * (0.85 + 0.3 * grain);
+                // Glazes multiply, like transparent pigment.
+                for (v, q) in c.iter_mut().zip([p.red(), p.green(), p.blue()]) {
+                    *v *= 1.0 - d + d * q;
+                }
+            }
+            Color::from_rgba(c[0], c[1], c[2], 1.0).unwrap()
+        })
+    }
+
+    /// The tileable image at `path` repeated over white, each tile `tile`
+    /// times the image size at display scale; plain white if the image
+    /// cannot be read.
+    pub fn paper(width: u32, height: u32, scale: f32, path: Option<&str>, tile: f32) -> Self {
+        let mut canvas = Canvas::new(width, height);
+        canvas.fill(*WHITE);
+        if let Some(img) = path.and_then(paper_tile) {
+            let k = tile.max(0.01) * scale;
+            let pattern = Pattern::new(
+                img.as_ref(),
+                SpreadMode::Repeat,
+                FilterQuality::Bilinear,
+                1.0,
+                Transform::from_scale(k, k),
+            );
+            Shape::new()
+                .rect_xywh(pt(0, 0), pt(width as f32, height as f32))
+                .fill_paint(&paint_shader(pattern))
+                .draw(&mut canvas);
+        }
+        BG(canvas)
+    }
+
+    /// The values of `grid` through `colors`, low to high, blended in
+    /// `space`.
+    pub fn field(
+        width: u32,
+        height: u32,
+        scale: f32,
+        grid: &ScalarGrid,
+        colors: &[Color],
+        space: Interpolation,
+    ) -> Self {
+        let (lo, hi) = grid.range();
+        let span = (hi - lo).max(1e-6);
+        Self::from_pixels(width, height, 0, |i, j, _| {
+            let v = grid.value((i as f32 + 0.5) / scale, (j as f32 + 0.5) / scale);
+            sample_colors(colors, (v - lo) / span, space)
+        })
+    }
+
+    /// The image `img`, already rotated, covering or fitted to the canvas,
+    /// then blurred, desaturated and tinted as `controls` says. White where
+    /// there is no image.
+    pub fn image(
+        width: u32,
+        height: u32,
+        scale: f32,
+        img: Option<&RgbaImage>,
+        controls: &BackgroundControls,
+    ) -> Self {
+        let Some(img) = img.filter(|i| i.width() > 0 && i.height() > 0) else {
+            return Self::solid(width, height, *WHITE);
+        };
+        let (iw, ih) = (img.width() as f32, img.height() as f32);
+        let (sx, sy) = (width as f32 / iw, height as f32 / ih);
+        let s = match controls.fit.unwrap_or(ImageFit::Cover) {
+            ImageFit::Cover => sx.max(sy),
+            ImageFit::Fit => sx.min(sy),
+        };
+        // Blur the source rather than the canvas, so the sigma shrinks with
+        // the image and large renders stay cheap.
+        let blurred;
+        let img = if controls.image_blur > 0.0 {
+            blurred = image::imageops::fast_blur(img, controls.image_blur * scale / s);
+            &blurred
+        } else {
+            img
+        };
+        let (ox, oy) = (
+            0.5 * (width as f32 - iw * s),
+            0.5 * (height as f32 - ih * s),
+        );
+        let (mw, mh) = (img.width() - 1, img.height() - 1);
+        let texel = |x: u32, y: u32| {
+            let p = img.get_pixel(x.min(mw), y.min(mh)).0;
+            p.map(|v| v as f32 / 255.0)
+        };
+        let tint = controls.tint;
+        let tint = [tint.r(), tint.g(), tint.b()].map(|v| v as f32 / 255.0);
+        let (desaturate, amount) = (
+            controls.desaturate.clamp(0.0, 1.0),
+            controls.tint_amount.clamp(0.0, 1.0),
+        );
+        Self::from_pixels(width, height, 0, |i, j, _| {
+            let x = (i as f32 + 0.5 - ox) / s;
+            let y = (j as f32 + 0.5 - oy) / s;
+            if x < 0.0 || y < 0.0 || x > iw || y > ih {
+                return *WHITE;
+            }
+            // Bilinear between the four nearest texel centers.
+            let (x, y) = ((x - 0.5).max(0.0), (y - 0.5).max(0.0));
+            let (x0, y0) = (x as u32, y as u32);
+            let (tx, ty) = (x.fract(), y.fract());
+            let (a, b) = (texel(x0, y0), texel(x0 + 1, y0));
+            let (c, d) = (texel(x0, y0 + 1), texel(x0 + 1, y0 + 1));
+            let p: [f32; 4] = std::array::from_fn(|k| {
+                let top = a[k] + (b[k] - a[k]) * tx;
+                let bottom = c[k] + (d[k] - c[k]) * tx;
+                top + (bottom - top) * ty
+            });
+            // Transparent parts of the image show white paper.
+            let mut rgb = [0, 1, 2].map(|k| p[k] * p[3] + 1.0 - p[3]);
+            let luma = 0.2126 * rgb[0] + 0.7152 * rgb[1] + 0.0722 * rgb[2];
+            for (v, t) in rgb.iter_mut().zip(tint) {
+                *v += (luma - *v) * desaturate;
+                *v *= 1.0 - amount + amount * t;
+            }
+            Color::from_rgba(rgb[0], rgb[1], rgb[2], 1.0).unwrap_or(*WHITE)
+        })
+    }
+
     pub fn bg(&self) -> Paint<'_> {
         let pattern = Pattern::new(
             (self.0).pixmap.as_ref(),
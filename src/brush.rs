@@ -0,0 +1,179 @@
+//! The Brush style: a bristle tip dragged along each curve. Every bristle
+//! leaves its own streak, carries its own load of paint that runs out along
+//! the stroke, and skips where the brush goes dry.
+
+use crate::gui::{section, SliderRow, SPACE};
+use crate::size::{SizeControls, SizeFn};
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+use wassily::prelude::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct BrushControls {
+    /// Half the width of the brush.
+    pub size_controls: SizeControls,
+    pub bristles: u32,
+    /// How far bristles stray from even spacing across the brush.
+    pub spread: f32,
+    /// How readily bristles skip as their paint runs low; 0 is a wet brush.
+    pub dry: f32,
+    /// How much of its paint the brush spends over the stroke; 0 never fades.
+    pub load: f32,
+}
+
+impl Default for BrushControls {
+    fn default() -> Self {
+        Self {
+            size_controls: SizeControls::default()
+                .set_size_fn(Some(SizeFn::Constant))
+                .set_size(12.0)
+                .set_min_size(2.0),
+            bristles: 16,
+            spread: 0.5,
+            dry: 0.3,
+            load: 0.6,
+        }
+    }
+}
+
+impl BrushControls {
+    pub fn ui(&mut self, ui: &mut egui::Ui) {
+        section(ui, "Brush");
+        egui::Grid::new("brush")
+            .spacing((15.0, 10.0))
+            .min_col_width(90.0)
+            .show(ui, |ui| {
+                SliderRow::new("Bristles", &mut self.bristles, 16, 1..=64)
+                    .hover(&["Streaks the brush", "leaves side by side."])
+                    .show(ui);
+                SliderRow::new("Spread", &mut self.spread, 0.5, 0.0..=1.0)
+                    .hover(&["How far bristles stray from", "even spacing across the brush."])
+                    .steps(0.05, 0.1)
+                    .decimals(2)
+                    .show(ui);
+                SliderRow::new("Dry", &mut self.dry, 0.3, 0.0..=1.0)
+                    .hover(&["How readily bristles skip as", "their paint runs low."])
+                    .steps(0.05, 0.1)
+                    .decimals(2)
+                    .show(ui);
+                SliderRow::new("Load", &mut self.load, 0.6, 0.0..=1.0)
+                    .hover(&["How much paint the brush spends", "over a stroke; 0 never fades."])
+                    .steps(0.05, 0.1)
+                    .decimals(2)
+                    .show(ui);
+            });
+        ui.add_space(SPACE);
+        self.size_controls.ui(ui);
+    }
+
+    /// Drag the brush along `pts`, skipping any segment longer than
+    /// `max_jump`. `radius(i)` is the half-width and `color(i)` the paint at
+    /// point i, laid down in `blend`; all randomness comes from the curve's
+    /// own `rng`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn paint(
+        &self,
+        pts: &[Point],
+        max_jump: f32,
+        radius: impl Fn(usize) -> f32,
+        color: impl Fn(usize) -> Color,
+        opacity: f32,
+        blend: BlendMode,
+        rng: &mut SmallRng,
+        canvas: &mut Canvas,
+    ) {
+        let n = pts.len();
+        if n < 2 {
+            return;
+        }
+        let mut arc = Vec::with_capacity(n);
+        let mut s = 0.0;
+        for i in 0..n {
+            if i > 0 {
+                s += pts[i].dist2(pts[i - 1]).sqrt();
+            }
+            arc.push(s);
+        }
+        let len = s.max(f32::EPSILON);
+        let normals: Vec<(f32, f32)> = (0..n)
+            .map(|i| {
+                let prev = pts[i.saturating_sub(1)];
+                let next = pts[(i + 1).min(n - 1)];
+                let (tx, ty) = (next.x - prev.x, next.y - prev.y);
+                let tl = (tx * tx + ty * ty).sqrt().max(f32::EPSILON);
+                (-ty / tl, tx / tl)
+            })
+            .collect();
+        let count = self.bristles.max(1);
+        for b in 0..count {
+            // Position across the brush in [-1, 1].
+            let even = if count == 1 {
+                0.0
+            } else {
+                -1.0 + 2.0 * b as f32 / (count - 1) as f32
+            };
+            let across = (even + self.spread * rng.random_range(-1.0..1.0) / count as f32 * 2.0)
+                .clamp(-1.0, 1.0);
+            let thickness = rng.random_range(0.6..1.4) * 1.2 / count as f32;
+            // Bristles hold a little more or less paint, so they run out at
+            // different points, and each is a shade lighter or darker.
+            let capacity = rng.random_range(0.7..1.3f32);
+            let shade = rng.random_range(-0.12..0.12f32);
+            let mut dry_run = false;
+            for i in 0..n - 1 {
+                if pts[i].dist2(pts[i + 1]) > max_jump * max_jump {
+                    continue;
+                }
+                let paint = (1.0 - self.load * arc[i] / len / capacity).clamp(0.0, 1.0);
+                if self.dry > 0.0 {
+                    // Dry streaks start more often, and last longer, as the
+                    // paint runs out.
+                    let u = rng.random::<f32>();
+                    dry_run = if dry_run {
+                        u > 0.35 * paint.max(0.1)
+                    } else {
+                        u < 0.25 * self.dry * (1.2 - paint)
+                    };
+                    if dry_run {
+                        continue;
+                    }
+                }
+                let alpha = opacity * paint;
+                if alpha < 0.01 {
+                    continue;
+                }
+                let (r0, r1) = (radius(i), radius(i + 1));
+                let (n0, n1) = (normals[i], normals[i + 1]);
+                let a = pt(pts[i].x + across * r0 * n0.0, pts[i].y + across * r0 * n0.1);
+                let z = pt(
+                    pts[i + 1].x + across * r1 * n1.0,
+                    pts[i + 1].y + across * r1 * n1.1,
+                );
+                let c = color(i);
+                let tone = |v: f32| {
+                    if shade > 0.0 {
+                        v + (1.0 - v) * shade
+                    } else {
+                        v * (1.0 + shade)
+                    }
+                };
+                let c = Color::from_rgba(
+                    tone(c.red()),
+                    tone(c.green()),
+                    tone(c.blue()),
+                    c.alpha() * alpha,
+                )
+                .unwrap_or(c);
+                let mut paint = Paint::default();
+                paint.anti_alias = true;
+                paint.set_color(c);
+                paint.blend_mode = blend;
+                Shape::new()
+                    .line(a, z)
+                    .stroke_paint(&paint)
+                    .stroke_weight(thickness * (r0 + r1))
+                    .draw(canvas);
+            }
+        }
+    }
+}
@@ -1,34 +1,156 @@
 #![allow(dead_code)]
 
-use crate::gui::{color_picker, pick_list, section, SliderRow};
+use crate::gui::{color_picker, pick_list, section, SliderRow, SPACE};
+use crate::harmony::{HarmonyControls, UserPalette};
 use eframe::egui;
 use serde::{Deserialize, Serialize};
-use wassily::prelude::palette::{Darken, Desaturate, OklabHue, Saturate};
+use wassily::prelude::palette::{
+    Darken, Desaturate, IntoColor, LinSrgb, Oklab, OklabHue, Oklch, Saturate,
+};
 use wassily::prelude::*;
 
-pub fn color_scale(color1: Color, color2: Color, n: u8) -> Vec<Color> {
+/// The color space colors are blended in, wherever the palette or scale is
+/// interpolated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Interpolation {
+    /// Straight blends of the stored values; mixes can look muddy.
+    Srgb,
+    /// Physically even light; mixes look bright.
+    Linear,
+    /// Perceptually even steps without a hue turn.
+    Oklab,
+    /// Oklab lightness and chroma with the hue turning the short way round.
+    OklchShorter,
+    /// As OklchShorter, turning the long way round the hue circle.
+    OklchLonger,
+    /// Okhsl hue, saturation and lightness each blended straight, the hue
+    /// never wrapping past 0; the original scale ramp.
+    Okhsl,
+}
+
+impl std::fmt::Display for Interpolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Interpolation::Srgb => "sRGB",
+                Interpolation::Linear => "Linear RGB",
+                Interpolation::Oklab => "Oklab",
+                Interpolation::OklchShorter => "Oklch Shorter",
+                Interpolation::OklchLonger => "Oklch Longer",
+                Interpolation::Okhsl => "Okhsl",
+            }
+        )
+    }
+}
+
+/// Below this Oklch chroma a color is treated as gray, and its hue is
+/// ignored.
+const ACHROMATIC: f32 = 1e-3;
+
+/// The signed turn in degrees from hue `h1` to `h2`; Shorter stays within
+/// half a turn and Longer goes at least half a turn the other way.
+pub fn hue_delta(h1: f32, h2: f32, longer: bool) -> f32 {
+    let d = (h2 - h1).rem_euclid(360.0);
+    match (longer, d) {
+        (false, d) if d > 180.0 => d - 360.0,
+        (true, d) if d > 0.0 && d < 180.0 => d - 360.0,
+        (_, d) => d,
+    }
+}
+
+/// The Okhsl color `t` of the way from `a` to `b`, each component blended
+/// straight across.
+fn okhsl_lerp(a: Okhsl, b: Okhsl, t: f32) -> Okhsl {
+    let h = (1.0 - t) * a.hue.into_positive_radians() + t * b.hue.into_positive_radians();
+    let s = (1.0 - t) * a.saturation + t * b.saturation;
+    let l = (1.0 - t) * a.lightness + t * b.lightness;
+    Okhsl::new(OklabHue::from_radians(h), s, l)
+}
+
+impl Interpolation {
+    /// The color `t` of the way from `a` to `b`.
+    pub fn mix(self, a: Color, b: Color, t: f32) -> Color {
+        let srgb = |c: Color| Srgb::new(c.red(), c.green(), c.blue());
+        let (p, q) = (srgb(a), srgb(b));
+        let lerp = |x: f32, y: f32| x + (y - x) * t;
+        let rgb: Srgb = match self {
+            Interpolation::Srgb => Srgb::new(
+                lerp(p.red, q.red),
+                lerp(p.green, q.green),
+                lerp(p.blue, q.blue),
+            ),
+            Interpolation::Linear => {
+                let (p, q): (LinSrgb, LinSrgb) = (p.into_linear(), q.into_linear());
+                Srgb::from_linear(LinSrgb::new(
+                    lerp(p.red, q.red),
+                    lerp(p.green, q.green),
+                    lerp(p.blue, q.blue),
+                ))
+            }
+            Interpolation::Oklab => {
+                let (p, q): (Oklab, Oklab) = (p.into_color(), q.into_color());
+                Oklab::new(lerp(p.l, q.l), lerp(p.a, q.a), lerp(p.b, q.b)).into_color()
+            }
+            Interpolation::OklchShorter | Interpolation::OklchLonger => {
+                let (p, q): (Oklch, Oklch) = (p.into_color(), q.into_color());
+                // A gray takes the hue of the other color, so blends toward
+                // white or black do not swing through unrelated hues.
+                let (mut h1, mut h2) = (p.hue.into_degrees(), q.hue.into_degrees());
+                if p.chroma < ACHROMATIC {
+                    h1 = h2;
+                }
+                if q.chroma < ACHROMATIC {
+                    h2 = h1;
+                }
+                let d = hue_delta(h1, h2, self == Interpolation::OklchLonger);
+                Oklch::new(lerp(p.l, q.l), lerp(p.chroma, q.chroma), h1 + d * t).into_color()
+            }
+            Interpolation::Okhsl => {
+                let (p, q): (Okhsl, Okhsl) = (p.into_color(), q.into_color());
+                okhsl_lerp(p, q, t).into_color()
+            }
+        };
+        Color::from_rgba(
+            rgb.red.clamp(0.0, 1.0),
+            rgb.green.clamp(0.0, 1.0),
+            rgb.blue.clamp(0.0, 1.0),
+            lerp(a.alpha(), b.alpha()),
+        )
+        .unwrap_or(a)
+    }
+}
+
+/// `n` colors from a lightened `color1` to a darkened `color2`, blended in
+/// `space`.
+pub fn color_scale(color1: Color, color2: Color, n: u8, space: Interpolation) -> Vec<Color> {
     let c1 = Okhsl::from_color(&color1);
     let c2 = Okhsl::from_color(&color2);
     let hsl1 = c1.desaturate(0.5).lighten(0.5);
     let hsl2 = c2.saturate(0.5).darken(0.5);
+    let (start, end) = (hsl1.to_color(), hsl2.to_color());
     (0..n)
         .map(|p| {
             let t = p as f32 * 1.0 / (n - 1) as f32;
-            let h =
-                (1.0 - t) * hsl1.hue.into_positive_radians() + t * hsl2.hue.into_positive_radians();
-            let s = (1.0 - t) * hsl1.saturation + t * hsl2.saturation;
-            let l = (1.0 - t) * hsl1.lightness + t * hsl2.lightness;
-            Okhsl::new(OklabHue::from_radians(h), s, l).to_color()
+            // Okhsl blends the endpoints as they are, without a round trip
+            // through RGB, so the original ramp is reproduced exactly.
+            if space == Interpolation::Okhsl {
+                okhsl_lerp(hsl1, hsl2, t).to_color()
+            } else {
+                space.mix(start, end, t)
+            }
         })
         .collect()
 }
 
-pub fn expand_palette(palette: Vec<Color>) -> Vec<Color> {
+/// The palette followed by the midpoints of every pair of its colors.
+pub fn expand_palette(palette: Vec<Color>, space: Interpolation) -> Vec<Color> {
     let mut result = palette.clone();
     let n = palette.len();
     for i in 0..n {
         for j in i..n {
-            let c = result[i].lerp(&result[j], 0.5);
+            let c = space.mix(result[i], result[j], 0.5);
             result.push(c);
         }
     }
@@ -46,7 +168,7 @@ fn hex_to_color(hex: Vec<u32>) -> Vec<Color> {
 
 fn make_palette(hex: Vec<u32>) -> Palette {
     let raw_palette = hex_to_color(hex);
-    Palette::new(expand_palette(raw_palette))
+    Palette::new(expand_palette(raw_palette, Interpolation::Srgb))
 }
 
 const GRAYS: [u8; 8] = [202, 168, 135, 109, 95, 74, 61, 28];
@@ -105,13 +227,16 @@ pub fn swatches() -> Vec<egui::Color32> {
     out
 }
 
-/// The full (expanded) color list a palette choice provides.
-pub fn palette_colors(pal: Palettes) -> Vec<Color> {
+/// The full (expanded) color list a palette choice provides, with the
+/// in-between colors blended in `space`.
+pub fn palette_colors(pal: Palettes, space: Interpolation) -> Vec<Color> {
     use Palettes::*;
     match pal {
         GrayScale => gray_values(),
         Royalty | DeltaBlues | PinotNoir | Algae | Scepter | Fire | Perfume | Rose | PorcoRosso
-        | SpiritedAway => expand_palette(hex_to_color(pal.base_hex().to_vec())),
+        | SpiritedAway => {
+            expand_palette(hex_to_color(pal.base_hex().to_vec()), space)
+        }
         MonoBlue => {
             let mut cs = gray_values();
             cs.push(*ROYALBLUE);
@@ -131,18 +256,18 @@ pub fn palette_colors(pal: Palettes) -> Vec<Color> {
 }
 
 pub fn color_palette(pal: Palettes) -> Palette {
-    Palette::new(palette_colors(pal))
+    Palette::new(palette_colors(pal, Interpolation::Srgb))
 }
 
-/// Interpolate a color list at t in [0, 1].
-pub fn sample_colors(colors: &[Color], t: f32) -> Color {
+/// Interpolate a color list at t in [0, 1], blending in `space`.
+pub fn sample_colors(colors: &[Color], t: f32, space: Interpolation) -> Color {
     match colors.len() {
         0 => *WHITE,
         n => {
             let x = t.clamp(0.0, 1.0) * (n - 1) as f32;
             let i = x.floor() as usize;
             let j = (i + 1).min(n - 1);
-            colors[i].lerp(&colors[j], x - i as f32)
+            space.mix(colors[i], colors[j], x - i as f32)
         }
     }
 }
@@ -189,7 +314,187 @@ impl std::fmt::Display for Palettes {
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum ColorMode {
     Palette,
+    /// The legacy ramp between two anchors, lightened at one end and
+    /// darkened at the other.
     Scale,
+    /// A custom gradient through any number of stops.
+    Gradient,
+    /// A palette generated from a base color by a harmony rule.
+    Harmony,
+    /// A palette saved from the harmony generator.
+    User,
+}
+
+impl std::fmt::Display for ColorMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                ColorMode::Palette => "Palette",
+                ColorMode::Scale => "Scale",
+                ColorMode::Gradient => "Gradient",
+                ColorMode::Harmony => "Harmony",
+                ColorMode::User => "User Palette",
+            }
+        )
+    }
+}
+
+/// A color stop of a custom gradient.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct GradientStop {
+    /// Where the stop sits, from 0 at the start of the gradient to 1 at the
+    /// end.
+    pub position: f32,
+    pub color: egui::Color32,
+}
+
+impl GradientStop {
+    pub fn new(position: f32, color: egui::Color32) -> Self {
+        Self { position, color }
+    }
+}
+
+/// How many evenly spaced colors stand in for a custom gradient.
+const GRADIENT_STEPS: usize = 64;
+
+pub fn to_color(c: egui::Color32) -> Color {
+    Color::from_rgba8(c.r(), c.g(), c.b(), 255)
+}
+
+pub fn to_color32(c: Color) -> egui::Color32 {
+    let c = c.to_color_u8();
+    egui::Color32::from_rgb(c.red(), c.green(), c.blue())
+}
+
+/// The gradient through `stops` at t in [0, 1], blended in `space`. Stops
+/// need not be in order; beyond the end stops the color holds.
+pub fn sample_stops(stops: &[GradientStop], t: f32, space: Interpolation) -> Color {
+    let mut sorted = stops.to_vec();
+    sorted.sort_by(|a, b| a.position.total_cmp(&b.position));
+    let t = t.clamp(0.0, 1.0);
+    let k = sorted.partition_point(|s| s.position <= t);
+    match (k.checked_sub(1).map(|i| sorted[i]), sorted.get(k)) {
+        (None, None) => *WHITE,
+        (Some(a), None) => to_color(a.color),
+        (None, Some(b)) => to_color(b.color),
+        (Some(a), Some(b)) => {
+            let span = (b.position - a.position).max(1e-6);
+            space.mix(to_color(a.color), to_color(b.color), (t - a.position) / span)
+        }
+    }
+}
+
+/// The custom gradient as a list of evenly spaced colors, so it can be used
+/// wherever a palette is.
+pub fn gradient_colors(stops: &[GradientStop], space: Interpolation) -> Vec<Color> {
+    (0..GRADIENT_STEPS)
+        .map(|k| sample_stops(stops, k as f32 / (GRADIENT_STEPS - 1) as f32, space))
+        .collect()
+}
+
+/// The gradient bar with a draggable handle under each stop, and rows to
+/// edit the selected stop. Clicking the bar adds a stop there.
+fn gradient_editor(ui: &mut egui::Ui, stops: &mut Vec<GradientStop>, space: Interpolation) {
+    const BAR: f32 = 24.0;
+    const HANDLE: f32 = 7.0;
+    let id = ui.id().with("gradient stop");
+    let mut selected: usize = ui.data(|d| d.get_temp(id)).unwrap_or(0);
+
+    ui.add_space(SPACE);
+    let width = ui.available_width() - 2.0 * HANDLE - ui.spacing().item_spacing.x;
+    let bar = ui.horizontal(|ui| {
+        ui.add_space(HANDLE);
+        let (bar, response) =
+            ui.allocate_exact_size(egui::vec2(width, BAR), egui::Sense::click());
+        let painter = ui.painter();
+        let columns = width.max(1.0) as usize;
+        for k in 0..columns {
+            let t = k as f32 / (columns - 1).max(1) as f32;
+            let x = bar.left() + k as f32;
+            let column = egui::Rect::from_min_max(
+                egui::pos2(x, bar.top()),
+                egui::pos2(x + 1.0, bar.bottom()),
+            );
+            painter.rect_filled(column, 0.0, to_color32(sample_stops(stops, t, space)));
+        }
+        if let Some(pos) = response.interact_pointer_pos().filter(|_| response.clicked()) {
+            let t = ((pos.x - bar.left()) / width).clamp(0.0, 1.0);
+            let color = to_color32(sample_stops(stops, t, space));
+            stops.push(GradientStop::new(t, color));
+            selected = stops.len() - 1;
+        }
+        bar
+    });
+
+    // The handles, one under each stop; drag to move, click to select.
+    let (strip, _) = ui.allocate_exact_size(
+        egui::vec2(ui.available_width(), 2.0 * HANDLE),
+        egui::Sense::hover(),
+    );
+    for (k, stop) in stops.iter_mut().enumerate() {
+        let x = bar.inner.left() + stop.position * width;
+        let handle = egui::Rect::from_center_size(
+            egui::pos2(x, strip.center().y),
+            egui::Vec2::splat(2.0 * HANDLE),
+        );
+        let response = ui.interact(handle, id.with(k), egui::Sense::click_and_drag());
+        if response.clicked() || response.drag_started() {
+            selected = k;
+        }
+        if response.dragged() {
+            stop.position = (stop.position + response.drag_delta().x / width).clamp(0.0, 1.0);
+        }
+        let top = egui::pos2(x, strip.top());
+        let points = vec![
+            top,
+            egui::pos2(x + HANDLE, strip.bottom()),
+            egui::pos2(x - HANDLE, strip.bottom()),
+        ];
+        let outline = if k == selected {
+            egui::Color32::ORANGE
+        } else {
+            egui::Color32::GRAY
+        };
+        ui.painter().add(egui::Shape::convex_polygon(
+            points,
+            stop.color,
+            egui::Stroke::new(1.5, outline),
+        ));
+    }
+
+    selected = selected.min(stops.len().saturating_sub(1));
+    if let Some(stop) = stops.get_mut(selected) {
+        ui.add_space(SPACE);
+        egui::Grid::new("gradient_stop")
+            .spacing((15.0, 10.0))
+            .min_col_width(90.0)
+            .show(ui, |ui| {
+                SliderRow::new("Position", &mut stop.position, 0.5, 0.0..=1.0)
+                    .hover(&["Where the selected stop", "sits along the gradient."])
+                    .steps(0.01, 0.05)
+                    .decimals(2)
+                    .show(ui);
+                color_picker(ui, "Stop Color", &mut stop.color);
+            });
+    }
+    ui.add_space(SPACE);
+    ui.horizontal(|ui| {
+        if ui
+            .add_enabled(stops.len() > 2, egui::Button::new("Remove Stop"))
+            .clicked()
+        {
+            stops.remove(selected);
+            selected = selected.saturating_sub(1);
+        }
+        if ui.button("Reverse").clicked() {
+            for stop in stops.iter_mut() {
+                stop.position = 1.0 - stop.position;
+            }
+        }
+    });
+    ui.data_mut(|d| d.insert_temp(id, selected));
 }
 
 /// How each curve's color is chosen from the palette or scale.
@@ -244,7 +549,23 @@ fn default_region_colors() -> u32 {
     6
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+fn default_interpolation() -> Option<Interpolation> {
+    Some(Interpolation::Srgb)
+}
+
+fn default_scale_interpolation() -> Option<Interpolation> {
+    Some(Interpolation::Okhsl)
+}
+
+fn default_stops() -> Vec<GradientStop> {
+    vec![
+        GradientStop::new(0.0, egui::Color32::from_rgb(242, 213, 208)),
+        GradientStop::new(0.5, egui::Color32::from_rgb(20, 134, 187)),
+        GradientStop::new(1.0, egui::Color32::from_rgb(10, 14, 32)),
+    ]
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ColorControls {
     pub mode: Option<ColorMode>,
     pub anchor1: egui::Color32,
@@ -270,6 +591,23 @@ pub struct ColorControls {
     /// Position and Radial: run the palette in the opposite direction.
     #[serde(default)]
     pub reverse: bool,
+    /// The space the palette and gradients blend colors in, and colors are
+    /// sampled between.
+    #[serde(default = "default_interpolation")]
+    pub interpolation: Option<Interpolation>,
+    /// Scale: the space the ramp between the anchors blends in; Okhsl, the
+    /// original ramp, unless chosen otherwise.
+    #[serde(default = "default_scale_interpolation")]
+    pub scale_interpolation: Option<Interpolation>,
+    /// Gradient: the stops of the custom gradient.
+    #[serde(default = "default_stops")]
+    pub stops: Vec<GradientStop>,
+    #[serde(default)]
+    pub harmony: HarmonyControls,
+    /// User: the chosen user palette, kept here so a render or a saved
+    /// parameter file does not depend on the palette library.
+    #[serde(default)]
+    pub user_palette: UserPalette,
 }
 
 impl Default for ColorControls {
@@ -286,6 +624,11 @@ impl Default for ColorControls {
             region_scale: 1.5,
             region_colors: 6,
             reverse: false,
+            interpolation: Some(Interpolation::Srgb),
+            scale_interpolation: Some(Interpolation::Okhsl),
+            stops: default_stops(),
+            harmony: HarmonyControls::default(),
+            user_palette: UserPalette::default(),
         }
     }
 }
@@ -311,19 +654,26 @@ impl ColorControls {
         self
     }
 
-    pub fn ui(&mut self, ui: &mut egui::Ui) {
+    /// The color rows; `library` holds the saved user palettes.
+    pub fn ui(&mut self, ui: &mut egui::Ui, library: &mut Vec<UserPalette>) {
         use Palettes::*;
         section(ui, "Color");
         egui::Grid::new("color_mode")
             .spacing((15.0, 10.0))
             .min_col_width(90.0)
             .show(ui, |ui| {
-                ui.label("Color Mode");
-                ui.horizontal(|ui| {
-                    ui.radio_value(&mut self.mode, Some(ColorMode::Palette), "Palette");
-                    ui.radio_value(&mut self.mode, Some(ColorMode::Scale), "Scale");
-                });
-                ui.end_row();
+                pick_list(
+                    ui,
+                    "Color Mode",
+                    &[
+                        ColorMode::Palette,
+                        ColorMode::Scale,
+                        ColorMode::Gradient,
+                        ColorMode::Harmony,
+                        ColorMode::User,
+                    ],
+                    &mut self.mode,
+                );
                 pick_list(
                     ui,
                     "Color By",
@@ -394,32 +744,153 @@ impl ColorControls {
                         .steps(1.0, 2.0)
                         .show(ui);
                 }
-                if self.mode == Some(ColorMode::Scale) {
-                    color_picker(ui, "Anchor 1", &mut self.anchor1);
-                    color_picker(ui, "Anchor 2", &mut self.anchor2);
-                } else {
-                    pick_list(
-                        ui,
-                        "Palette",
-                        &[
-                            Royalty,
-                            DeltaBlues,
-                            PinotNoir,
-                            Algae,
-                            Scepter,
-                            Fire,
-                            Perfume,
-                            Rose,
-                            GrayScale,
-                            PorcoRosso,
-                            SpiritedAway,
-                                                MonoRed,
-                            MonoGreen,
-                            MonoBlue,
-                        ],
-                        &mut self.palette_choice,
-                    );
+                pick_list(
+                    ui,
+                    "Blend In",
+                    &[
+                        Interpolation::Srgb,
+                        Interpolation::Linear,
+                        Interpolation::Oklab,
+                        Interpolation::OklchShorter,
+                        Interpolation::OklchLonger,
+                        Interpolation::Okhsl,
+                    ],
+                    &mut self.interpolation,
+                );
+                match self.mode {
+                    Some(ColorMode::Scale) => {
+                        color_picker(ui, "Anchor 1", &mut self.anchor1);
+                        color_picker(ui, "Anchor 2", &mut self.anchor2);
+                        pick_list(
+                            ui,
+                            "Scale Blend",
+                            &[
+                                Interpolation::Okhsl,
+                                Interpolation::Srgb,
+                                Interpolation::Linear,
+                                Interpolation::Oklab,
+                                Interpolation::OklchShorter,
+                                Interpolation::OklchLonger,
+                            ],
+                            &mut self.scale_interpolation,
+                        );
+                    }
+                    Some(ColorMode::User) => {
+                        ui.label("Palette");
+                        egui::ComboBox::from_id_salt("user_palette")
+                            .width(150.0)
+                            .selected_text(self.user_palette.name.as_str())
+                            .show_ui(ui, |ui| {
+                                for palette in library.iter() {
+                                    let name = palette.name.as_str();
+                                    let selected = self.user_palette.name == name;
+                                    if ui.selectable_label(selected, name).clicked() {
+                                        self.user_palette = palette.clone();
+                                    }
+                                }
+                            });
+                        ui.end_row();
+                        ui.label("");
+                        let saved = library.iter().position(|p| p.name == self.user_palette.name);
+                        if ui
+                            .add_enabled(saved.is_some(), egui::Button::new("Delete"))
+                            .on_hover_text("Remove the palette from the library.")
+                            .clicked()
+                        {
+                            if let Some(k) = saved {
+                                library.remove(k);
+                            }
+                        }
+                        ui.end_row();
+                    }
+                    Some(ColorMode::Gradient) | Some(ColorMode::Harmony) => {}
+                    _ => {
+                        pick_list(
+                            ui,
+                            "Palette",
+                            &[
+                                Royalty,
+                                DeltaBlues,
+                                PinotNoir,
+                                Algae,
+                                Scepter,
+                                Fire,
+                                Perfume,
+                                Rose,
+                                GrayScale,
+                                PorcoRosso,
+                                SpiritedAway,
+                                MonoRed,
+                                MonoGreen,
+                                MonoBlue,
+                            ],
+                            &mut self.palette_choice,
+                        );
+                    }
                 }
             });
+        match self.mode {
+            Some(ColorMode::Gradient) => {
+                let space = self.interpolation.unwrap_or(Interpolation::Srgb);
+                gradient_editor(ui, &mut self.stops, space);
+            }
+            Some(ColorMode::Harmony) => self.harmony.ui(ui, library),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn oklch(l: f32, chroma: f32, hue: f32) -> Color {
+        let rgb: Srgb = Oklch::new(l, chroma, hue).into_color();
+        let v = |x: f32| x.clamp(0.0, 1.0);
+        Color::from_rgba(v(rgb.red), v(rgb.green), v(rgb.blue), 1.0).unwrap()
+    }
+
+    fn hue(c: Color) -> f32 {
+        let lch: Oklch = Srgb::new(c.red(), c.green(), c.blue()).into_color();
+        lch.hue.into_positive_degrees()
+    }
+
+    /// The unsigned angle between two hues, in degrees.
+    fn hue_gap(h1: f32, h2: f32) -> f32 {
+        hue_delta(h1, h2, false).abs()
+    }
+
+    #[test]
+    fn hue_delta_across_zero() {
+        assert_eq!(hue_delta(350.0, 10.0, false), 20.0);
+        assert_eq!(hue_delta(350.0, 10.0, true), -340.0);
+        assert_eq!(hue_delta(10.0, 350.0, false), -20.0);
+    }
+
+    #[test]
+    fn hue_delta_exact_cases() {
+        assert_eq!(hue_delta(30.0, 30.0, false), 0.0);
+        assert_eq!(hue_delta(30.0, 30.0, true), 0.0);
+        assert_eq!(hue_delta(30.0, 210.0, false), 180.0);
+        assert_eq!(hue_delta(30.0, 210.0, true), 180.0);
+    }
+
+    #[test]
+    fn oklch_shorter_mixes_across_zero_on_the_short_arc() {
+        let (a, b) = (oklch(0.7, 0.1, 350.0), oklch(0.7, 0.1, 10.0));
+        let short = Interpolation::OklchShorter.mix(a, b, 0.5);
+        let long = Interpolation::OklchLonger.mix(a, b, 0.5);
+        assert!(hue_gap(hue(short), 0.0) < 2.0, "short {}", hue(short));
+        assert!(hue_gap(hue(long), 180.0) < 2.0, "long {}", hue(long));
+    }
+
+    #[test]
+    fn gray_takes_the_other_hue() {
+        let gray = Color::from_rgba(0.5, 0.5, 0.5, 1.0).unwrap();
+        let blue = oklch(0.6, 0.12, 250.0);
+        for t in [0.25, 0.5, 0.75] {
+            let mixed = Interpolation::OklchShorter.mix(gray, blue, t);
+            assert!(hue_gap(hue(mixed), 250.0) < 2.0, "t {t} hue {}", hue(mixed));
+        }
     }
 }
@@ -4,15 +4,28 @@ use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::{mpsc, Arc};
 
 use crate::art::draw;
-use crate::background::Background;
+use crate::background::{Background, BackgroundControls};
+use crate::brush::BrushControls;
 use crate::color::ColorControls;
+use crate::density::DensityControls;
 use crate::dot::DotControls;
+use crate::effects::EffectsControls;
 use crate::extrude::ExtrudeControls;
 use crate::fractal::FractalControls;
+use crate::harmony::UserPalette;
+use crate::hatch::HatchControls;
 use crate::imgnoise::{ImageNoiseControls, ThumbCache};
+use crate::isoline::IsolineControls;
+use crate::layer::LayerControls;
+use crate::magnitude::MagnitudeControls;
 use crate::noise::{NoiseControls, TurbulenceControls, WorleyControls};
 use crate::presets::Preset;
+use crate::relief::ReliefControls;
+use crate::ring::RingControls;
 use crate::sine::SineControls;
+use crate::stops::StopControls;
+use crate::stroke::StrokeControls;
+use crate::symmetry::SymmetryControls;
 
 use crate::{location::Location, presets::ribbons};
 use eframe::egui;
@@ -23,6 +36,8 @@ pub const HEIGHT: u32 = 1000;
 pub const SEED: u64 = 98713;
 /// Scale of the fast preview rendered while the full image is in flight.
 pub const PREVIEW_SCALE: f32 = 0.5;
+/// Storage key of the user palette library.
+pub const USER_PALETTES_KEY: &str = "user_palettes";
 
 /// A finished render arriving from a worker thread.
 pub struct RenderMsg {
@@ -47,6 +62,10 @@ pub struct K2 {
     pub pending_draw: bool,
     /// Thumbnail of the image noise source shown in the right panel.
     pub image_thumb: ThumbCache,
+    /// The layer the panels edit; 0 is the base layer.
+    pub layer: usize,
+    /// Palettes saved from the harmony generator, kept across sessions.
+    pub user_palettes: Vec<UserPalette>,
     epoch: Arc<AtomicU64>,
     tx: mpsc::Sender<RenderMsg>,
     rx: mpsc::Receiver<RenderMsg>,
@@ -65,6 +84,8 @@ impl K2 {
             rendering: false,
             pending_draw: false,
             image_thumb: ThumbCache::default(),
+            layer: 0,
+            user_palettes: Vec::new(),
             epoch: Arc::new(AtomicU64::new(0)),
             tx,
             rx,
@@ -146,23 +167,58 @@ pub struct Controls {
     pub solid_color: egui::Color32,
     pub location: Option<Location>,
     pub density: f32,
+    /// Even location: a scalar map that varies the line separation.
+    #[serde(default)]
+    pub density_map: DensityControls,
     pub noise_controls: NoiseControls,
     #[serde(default)]
     pub turbulence: TurbulenceControls,
     #[serde(default)]
     pub worley: WorleyControls,
+    /// Polar or symmetric layout of the flow field around the canvas center.
+    #[serde(default)]
+    pub symmetry: SymmetryControls,
     pub fractal_controls: FractalControls,
     pub speed: f32,
+    /// Step length, turning speed and stroke width following a scalar map.
+    #[serde(default)]
+    pub magnitude: MagnitudeControls,
+    /// The streamline integration scheme.
+    #[serde(default = "default_integrator")]
+    pub integrator: Option<Integrator>,
+    /// Adaptive integration: largest position error per step, canvas units.
+    #[serde(default = "default_tolerance")]
+    pub tolerance: f32,
+    /// Higher-order integrators: keep the turning speed momentum.
+    #[serde(default = "default_momentum")]
+    pub momentum: bool,
+    /// Rules that end curves early; not used by the Even location.
+    #[serde(default)]
+    pub stops: StopControls,
     pub stroke_width: f32,
+    /// Line style: tapered, size-following or calligraphic ribbons.
+    #[serde(default)]
+    pub stroke_controls: StrokeControls,
     /// Curve opacity; below 1.0 overlapping curves build up color.
     #[serde(default = "default_opacity")]
     pub opacity: f32,
     pub background: Option<Background>,
+    /// Gradient, wash, paper and field backgrounds.
+    #[serde(default)]
+    pub background_controls: BackgroundControls,
     pub width: u32,
     pub height: u32,
     pub sin_controls: SineControls,
     pub dot_controls: DotControls,
     pub extrude_controls: ExtrudeControls,
+    #[serde(default)]
+    pub brush_controls: BrushControls,
+    #[serde(default)]
+    pub hatch_controls: HatchControls,
+    #[serde(default)]
+    pub ring_controls: RingControls,
+    #[serde(default)]
+    pub isoline_controls: IsolineControls,
     pub color_mode_controls: ColorControls,
     #[serde(default)]
     pub image_noise: ImageNoiseControls,
@@ -182,6 +238,31 @@ pub struct Controls {
     /// Strips: fraction of the channel between neighboring curves left as a gap.
     #[serde(default = "default_strip_gap")]
     pub strip_gap: f32,
+    /// Drop shadows and emboss lighting for this layer's curves.
+    #[serde(default)]
+    pub relief: ReliefControls,
+    /// Post-processing of the finished image.
+    #[serde(default)]
+    pub effects: EffectsControls,
+    /// This layer's name, visibility and opacity in the stack.
+    #[serde(default)]
+    pub layer: LayerControls,
+    /// Layers drawn over this one, bottom to top, sharing its canvas and
+    /// background; their own canvas settings and layers are unused.
+    #[serde(default)]
+    pub layers: Vec<Controls>,
+}
+
+fn default_integrator() -> Option<Integrator> {
+    Some(Integrator::Euler)
+}
+
+fn default_tolerance() -> f32 {
+    0.05
+}
+
+fn default_momentum() -> bool {
+    true
 }
 
 fn default_strip_gap() -> f32 {
@@ -204,6 +285,14 @@ impl Controls {
     pub fn new() -> Self {
         Self::default()
     }
+
+    /// Layer `k` of the stack; 0 is this base layer.
+    pub fn layer_mut(&mut self, k: usize) -> &mut Controls {
+        match k.min(self.layers.len()) {
+            0 => self,
+            k => &mut self.layers[k - 1],
+        }
+    }
 }
 
 impl Default for Controls {
@@ -221,17 +310,30 @@ impl Default for Controls {
             noise_controls: NoiseControls::default(),
             turbulence: TurbulenceControls::default(),
             worley: WorleyControls::default(),
+            symmetry: SymmetryControls::default(),
             density: 50.0,
+            density_map: DensityControls::default(),
             fractal_controls: FractalControls::default(),
             speed: 1.0,
+            magnitude: MagnitudeControls::default(),
+            integrator: Some(Integrator::Euler),
+            tolerance: 0.05,
+            momentum: true,
+            stops: StopControls::default(),
             stroke_width: 1.0,
+            stroke_controls: StrokeControls::default(),
             opacity: 1.0,
             background: Some(Background::LightFiber),
+            background_controls: BackgroundControls::default(),
             width: 1080,
             height: 1080,
             sin_controls: SineControls::default(),
             dot_controls: DotControls::default(),
             extrude_controls: ExtrudeControls::default(),
+            brush_controls: BrushControls::default(),
+            hatch_controls: HatchControls::default(),
+            ring_controls: RingControls::default(),
+            isoline_controls: IsolineControls::default(),
             color_mode_controls: ColorControls::default(),
             image_noise: ImageNoiseControls::default(),
             grain_amount: 0.3,
@@ -239,6 +341,10 @@ impl Default for Controls {
             line_shift: 0.0,
             column_angle: 0.0,
             strip_gap: 0.08,
+            relief: ReliefControls::default(),
+            effects: EffectsControls::default(),
+            layer: LayerControls::default(),
+            layers: Vec::new(),
         }
     }
 }
@@ -249,6 +355,14 @@ pub enum CurveStyle {
     Dots,
     Extrusion,
     Strips,
+    /// A bristle brush dragged along the curve.
+    Brush,
+    /// Short strokes across the curve, engraving style.
+    Hatch,
+    /// Closed shapes around each seed, deformed by the flow.
+    Rings,
+    /// Contour lines of the noise itself, not streamlines.
+    Isolines,
 }
 
 impl std::fmt::Display for CurveStyle {
@@ -261,6 +375,34 @@ impl std::fmt::Display for CurveStyle {
                 CurveStyle::Dots => "Dots",
                 CurveStyle::Extrusion => "Extrusion",
                 CurveStyle::Strips => "Strips",
+                CurveStyle::Brush => "Brush",
+                CurveStyle::Hatch => "Hatch",
+                CurveStyle::Rings => "Rings",
+                CurveStyle::Isolines => "Isolines",
+            }
+        )
+    }
+}
+
+/// How curves are integrated through the flow field.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Integrator {
+    Euler,
+    Midpoint,
+    Rk4,
+    Adaptive,
+}
+
+impl std::fmt::Display for Integrator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Integrator::Euler => "Euler",
+                Integrator::Midpoint => "Midpoint",
+                Integrator::Rk4 => "RK4",
+                Integrator::Adaptive => "Adaptive",
             }
         )
     }
@@ -0,0 +1,104 @@
+//! Variable density for evenly spaced streamlines: a scalar map over the
+//! canvas shrinks the separation where it is strong, so lines crowd into
+//! dark image regions, the center, or noise highs — streamline halftoning.
+//! The Hatch style uses the same maps to space its strokes.
+
+use crate::gui::{pick_list, section, SliderRow};
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum DensitySource {
+    Uniform,
+    /// The flow field's own noise value.
+    Noise,
+    /// Densest at the center, falling off toward the corners.
+    Radial,
+    /// Densest where the noise image is darkest.
+    Image,
+}
+
+impl std::fmt::Display for DensitySource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                DensitySource::Uniform => "Uniform",
+                DensitySource::Noise => "Noise Value",
+                DensitySource::Radial => "Radial",
+                DensitySource::Image => "Image Luminance",
+            }
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct DensityControls {
+    pub source: Option<DensitySource>,
+    /// The separation at full density, as a fraction of the base separation.
+    pub min_sep: f32,
+    /// Swap the dense and sparse regions.
+    pub invert: bool,
+    /// Gamma applied to the map; above 1 saves the density for the
+    /// strongest regions.
+    pub contrast: f32,
+}
+
+impl Default for DensityControls {
+    fn default() -> Self {
+        Self {
+            source: Some(DensitySource::Uniform),
+            min_sep: 0.25,
+            invert: false,
+            contrast: 1.0,
+        }
+    }
+}
+
+impl DensityControls {
+    pub fn ui(&mut self, ui: &mut egui::Ui, title: &str) {
+        use DensitySource::*;
+        section(ui, title);
+        egui::Grid::new(title)
+            .spacing((15.0, 10.0))
+            .min_col_width(90.0)
+            .show(ui, |ui| {
+                pick_list(
+                    ui,
+                    "Source",
+                    &[Uniform, Noise, Radial, Image],
+                    &mut self.source,
+                );
+                if self.source != Some(Uniform) {
+                    SliderRow::new("Min Spacing", &mut self.min_sep, 0.25, 0.05..=1.0)
+                        .hover(&[
+                            "Line separation at full density,",
+                            "as a fraction of the base.",
+                        ])
+                        .steps(0.05, 0.1)
+                        .decimals(2)
+                        .show(ui);
+                    SliderRow::new("Contrast", &mut self.contrast, 1.0, 0.25..=4.0)
+                        .hover(&["Above 1 keeps dense lines for", "the strongest regions only."])
+                        .steps(0.05, 0.25)
+                        .decimals(2)
+                        .logarithmic()
+                        .show(ui);
+                    ui.label("Invert").on_hover_ui(|ui| {
+                        ui.colored_label(egui::Color32::ORANGE, "Swap the dense and");
+                        ui.colored_label(egui::Color32::ORANGE, "sparse regions.");
+                    });
+                    ui.checkbox(&mut self.invert, "");
+                    ui.end_row();
+                }
+            });
+    }
+
+    /// The separation for map value `m` in [0, 1] given base separation `sep`.
+    pub fn sep(&self, sep: f32, m: f32) -> f32 {
+        let m = if self.invert { 1.0 - m } else { m };
+        let m = m.clamp(0.0, 1.0).powf(self.contrast.max(0.01));
+        sep * (1.0 - (1.0 - self.min_sep.clamp(0.01, 1.0)) * m)
+    }
+}
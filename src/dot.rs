@@ -1,13 +1,32 @@
-use crate::gui::{color_picker, numeric, pick_list};
+use std::sync::{Arc, OnceLock};
+
+use crate::background::image_button;
+use crate::gui::{color_picker, numeric, pick_list, SliderRow};
 use crate::size::SizeControls;
+use ab_glyph::{Font, FontArc, OutlineCurve};
 use eframe::egui;
+use image::RgbaImage;
 use serde::{Deserialize, Serialize};
+use wassily::prelude::*;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum DotStyle {
     Circle,
     Square,
     Pearl,
+    /// A regular polygon with `sides` corners.
+    Polygon,
+    /// A star with `sides` points.
+    Star,
+    Cross,
+    /// An ellipse stretched along the curve.
+    Ellipse,
+    /// A rounded bar stretched along the curve.
+    Capsule,
+    /// The characters of a string, one per dot, cycling.
+    Glyph,
+    /// A PNG image tinted by the curve color.
+    Sprite,
 }
 
 impl std::fmt::Display for DotStyle {
@@ -19,12 +38,19 @@ impl std::fmt::Display for DotStyle {
                 DotStyle::Circle => "Circle",
                 DotStyle::Square => "Square",
                 DotStyle::Pearl => "Pearl",
+                DotStyle::Polygon => "Polygon",
+                DotStyle::Star => "Star",
+                DotStyle::Cross => "Cross",
+                DotStyle::Ellipse => "Ellipse",
+                DotStyle::Capsule => "Capsule",
+                DotStyle::Glyph => "Glyph",
+                DotStyle::Sprite => "Sprite",
             }
         )
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct DotControls {
     pub dot_style: Option<DotStyle>,
     pub size_controls: SizeControls,
@@ -34,12 +60,67 @@ pub struct DotControls {
     #[serde(default = "default_stroke")]
     pub stroke: bool,
     pub dot_stroke_color: egui::Color32,
+    /// Polygon and Star: the number of corners or points.
+    #[serde(default = "default_sides")]
+    pub sides: u32,
+    /// Star: inner radius as a fraction of the outer radius.
+    #[serde(default = "default_inner")]
+    pub inner: f32,
+    /// Ellipse, Capsule and Cross: width across the curve as a fraction
+    /// of the length along it.
+    #[serde(default = "default_aspect")]
+    pub aspect: f32,
+    /// Glyph: the characters drawn, one per dot.
+    #[serde(default = "default_glyphs")]
+    pub glyphs: String,
+    /// Sprite: path to a PNG image.
+    #[serde(default)]
+    pub sprite: Option<String>,
+    /// Square and Pearl: turn with the curve tangent, an oriented Pearl
+    /// also stretched along the curve by `aspect`. Off by default, so
+    /// presets and saved settings keep their upright dots; the other shapes
+    /// always turn with the curve.
+    #[serde(default)]
+    pub orient: bool,
+    /// Distance between dots along the curve in pixels; 0 places a dot at
+    /// every integration point.
+    #[serde(default)]
+    pub arc_spacing: f32,
+    /// Largest random offset of a dot from the curve, in pixels.
+    #[serde(default)]
+    pub jitter: f32,
+    /// Draw only every Nth dot.
+    #[serde(default = "default_every")]
+    pub every: u32,
+    /// Probability that any one dot is left out.
+    #[serde(default)]
+    pub drop: f32,
 }
 
 fn default_stroke() -> bool {
     true
 }
 
+fn default_every() -> u32 {
+    1
+}
+
+fn default_sides() -> u32 {
+    5
+}
+
+fn default_inner() -> f32 {
+    0.5
+}
+
+fn default_aspect() -> f32 {
+    0.4
+}
+
+fn default_glyphs() -> String {
+    "K2".to_string()
+}
+
 impl Default for DotControls {
     fn default() -> Self {
         Self {
@@ -49,11 +130,31 @@ impl Default for DotControls {
             pearl_smoothness: 3,
             stroke: true,
             dot_stroke_color: egui::Color32::WHITE,
+            sides: 5,
+            inner: 0.5,
+            aspect: 0.4,
+            glyphs: default_glyphs(),
+            sprite: None,
+            orient: false,
+            arc_spacing: 0.0,
+            jitter: 0.0,
+            every: 1,
+            drop: 0.0,
         }
     }
 }
 
 impl DotControls {
+    /// Whether dots turn with the curve: the shapes made for it always do,
+    /// Square and Pearl only when `orient` is on.
+    pub fn turns(&self) -> bool {
+        match self.dot_style {
+            Some(DotStyle::Square) | Some(DotStyle::Pearl) => self.orient,
+            Some(DotStyle::Circle) | None => false,
+            _ => true,
+        }
+    }
+
     pub fn ui(&mut self, ui: &mut egui::Ui) {
         use DotStyle::*;
         let d = Self::default();
@@ -61,7 +162,60 @@ impl DotControls {
             .spacing((15.0, 10.0))
             .min_col_width(90.0)
             .show(ui, |ui| {
-                pick_list(ui, "Dot Style", &[Circle, Square, Pearl], &mut self.dot_style);
+                pick_list(
+                    ui,
+                    "Dot Style",
+                    &[
+                        Circle, Square, Pearl, Polygon, Star, Cross, Ellipse, Capsule, Glyph,
+                        Sprite,
+                    ],
+                    &mut self.dot_style,
+                );
+                if matches!(self.dot_style, Some(Polygon) | Some(Star)) {
+                    numeric(ui, "Sides", &mut self.sides, d.sides, 3..=12, 1.0, 0);
+                }
+                if self.dot_style == Some(Star) {
+                    SliderRow::new("Inner", &mut self.inner, d.inner, 0.1..=0.9)
+                        .hover(&["Inner radius as a fraction", "of the outer radius."])
+                        .steps(0.05, 0.1)
+                        .decimals(2)
+                        .show(ui);
+                }
+                let stretched = matches!(self.dot_style, Some(Cross) | Some(Ellipse) | Some(Capsule))
+                    || (self.dot_style == Some(Pearl) && self.orient);
+                if stretched {
+                    SliderRow::new("Aspect", &mut self.aspect, d.aspect, 0.05..=1.0)
+                        .hover(&["Width across the curve as a", "fraction of the length."])
+                        .steps(0.05, 0.1)
+                        .decimals(2)
+                        .show(ui);
+                }
+                if self.dot_style == Some(Glyph) {
+                    ui.label("Text").on_hover_ui(|ui| {
+                        ui.colored_label(egui::Color32::ORANGE, "Characters drawn one per");
+                        ui.colored_label(egui::Color32::ORANGE, "dot, cycling along the curve.");
+                    });
+                    ui.text_edit_singleline(&mut self.glyphs);
+                    ui.end_row();
+                }
+                if self.dot_style == Some(Sprite) {
+                    ui.label("Image");
+                    image_button(
+                        ui,
+                        &mut self.sprite,
+                        ["Click to select a PNG,", "tinted by the curve color."],
+                        ("PNG image", &["png"]),
+                    );
+                    ui.end_row();
+                }
+                if matches!(self.dot_style, Some(Square) | Some(Pearl)) {
+                    ui.label("Orient").on_hover_ui(|ui| {
+                        ui.colored_label(egui::Color32::ORANGE, "Turn each dot with the");
+                        ui.colored_label(egui::Color32::ORANGE, "direction of the curve.");
+                    });
+                    ui.checkbox(&mut self.orient, "");
+                    ui.end_row();
+                }
                 ui.label("Stroke").on_hover_ui(|ui| {
                     ui.colored_label(
                         egui::Color32::ORANGE,
@@ -75,6 +229,29 @@ impl DotControls {
                     color_picker(ui, "Stroke Color", &mut self.dot_stroke_color);
                 }
             });
+        egui::Grid::new("dot_placement")
+            .spacing((15.0, 10.0))
+            .min_col_width(90.0)
+            .show(ui, |ui| {
+                SliderRow::new("Arc Spacing", &mut self.arc_spacing, 0.0, 0.0..=100.0)
+                    .hover(&["Pixels between dots along the", "curve; 0 is every step."])
+                    .steps(0.5, 5.0)
+                    .decimals(1)
+                    .show(ui);
+                SliderRow::new("Jitter", &mut self.jitter, 0.0, 0.0..=50.0)
+                    .hover(&["Largest random offset of a", "dot from the curve, pixels."])
+                    .steps(0.5, 5.0)
+                    .decimals(1)
+                    .show(ui);
+                SliderRow::new("Every", &mut self.every, 1, 1..=10)
+                    .hover(&["Draw only every Nth dot."])
+                    .show(ui);
+                SliderRow::new("Drop", &mut self.drop, 0.0, 0.0..=0.95)
+                    .hover(&["Chance that any one dot", "is left out."])
+                    .steps(0.05, 0.1)
+                    .decimals(2)
+                    .show(ui);
+            });
         self.size_controls.ui(ui);
         if self.dot_style == Some(Pearl) {
             egui::Grid::new("pearl")
@@ -103,3 +280,223 @@ impl DotControls {
         }
     }
 }
+
+impl DotControls {
+    /// The closed outline of a Square, Polygon, Star, Cross, Ellipse or
+    /// Capsule dot of radius `r` centered on `c`, turned by `angle`; empty
+    /// for the other styles.
+    pub fn vertices(&self, style: DotStyle, c: Point, r: f32, angle: f32) -> Vec<Point> {
+        let n = self.sides.max(3) as usize;
+        let b = self.aspect * r;
+        // Local coordinates: u along the curve, v across it.
+        let local: Vec<(f32, f32)> = match style {
+            DotStyle::Square => vec![(r, r), (-r, r), (-r, -r), (r, -r)],
+            DotStyle::Polygon => (0..n)
+                .map(|k| {
+                    let t = TAU * k as f32 / n as f32;
+                    (r * t.cos(), r * t.sin())
+                })
+                .collect(),
+            DotStyle::Star => (0..2 * n)
+                .map(|k| {
+                    let t = PI * k as f32 / n as f32;
+                    let rr = if k % 2 == 0 { r } else { r * self.inner };
+                    (rr * t.cos(), rr * t.sin())
+                })
+                .collect(),
+            DotStyle::Cross => {
+                let b = 0.5 * b;
+                vec![
+                    (r, b),
+                    (b, b),
+                    (b, r),
+                    (-b, r),
+                    (-b, b),
+                    (-r, b),
+                    (-r, -b),
+                    (-b, -b),
+                    (-b, -r),
+                    (b, -r),
+                    (b, -b),
+                    (r, -b),
+                ]
+            }
+            DotStyle::Ellipse => (0..32)
+                .map(|k| {
+                    let t = TAU * k as f32 / 32.0;
+                    (r * t.cos(), b * t.sin())
+                })
+                .collect(),
+            DotStyle::Capsule => {
+                let rr = b.min(r);
+                let d = r - rr;
+                (0..=12)
+                    .map(|k| {
+                        let t = -PI / 2.0 + PI * k as f32 / 12.0;
+                        (d + rr * t.cos(), rr * t.sin())
+                    })
+                    .chain((0..=12).map(|k| {
+                        let t = PI / 2.0 + PI * k as f32 / 12.0;
+                        (-d + rr * t.cos(), rr * t.sin())
+                    }))
+                    .collect()
+            }
+            _ => vec![],
+        };
+        place(&local, c, angle)
+    }
+
+    /// An oriented pearl: a lumpy polygon of `pearl_sides` corners rounded
+    /// `pearl_smoothness` times, stretched along the curve by `aspect`.
+    pub fn pearl(&self, c: Point, r: f32, angle: f32, rng: &mut SmallRng) -> Vec<Point> {
+        let n = self.pearl_sides.max(3) as usize;
+        let b = self.aspect.max(0.05) * r;
+        let mut local: Vec<(f32, f32)> = (0..n)
+            .map(|k| {
+                let t = TAU * (k as f32 + rng.random_range(-0.25..0.25)) / n as f32;
+                let s = rng.random_range(0.8..1.0);
+                (s * r * t.cos(), s * b * t.sin())
+            })
+            .collect();
+        // Chaikin corner cutting.
+        for _ in 0..self.pearl_smoothness {
+            local = (0..local.len())
+                .flat_map(|k| {
+                    let (a, b) = (local[k], local[(k + 1) % local.len()]);
+                    [
+                        (0.75 * a.0 + 0.25 * b.0, 0.75 * a.1 + 0.25 * b.1),
+                        (0.25 * a.0 + 0.75 * b.0, 0.25 * a.1 + 0.75 * b.1),
+                    ]
+                })
+                .collect();
+        }
+        place(&local, c, angle)
+    }
+}
+
+/// Local (along, across) coordinates turned by `angle` and moved to `c`,
+/// with the first vertex repeated so a stroke closes the outline.
+fn place(local: &[(f32, f32)], c: Point, angle: f32) -> Vec<Point> {
+    let (sin, cos) = angle.sin_cos();
+    let mut pts: Vec<Point> = local
+        .iter()
+        .map(|(u, v)| pt(c.x + u * cos - v * sin, c.y + u * sin + v * cos))
+        .collect();
+    if let Some(&first) = pts.first() {
+        pts.push(first);
+    }
+    pts
+}
+
+/// The font glyph dots are drawn with: egui's built-in proportional face.
+fn font() -> Option<&'static FontArc> {
+    static FONT: OnceLock<Option<FontArc>> = OnceLock::new();
+    FONT.get_or_init(|| {
+        let fonts = egui::FontDefinitions::default();
+        let data = fonts.font_data.get("Ubuntu-Light")?;
+        FontArc::try_from_vec(data.font.to_vec()).ok()
+    })
+    .as_ref()
+}
+
+/// The outline of character `ch` fitted in a circle of radius `r` centered
+/// on `c` and turned by `angle`, or None if the font has no outline for it.
+pub fn glyph_path(ch: char, c: Point, r: f32, angle: f32) -> Option<Path> {
+    let font = font()?;
+    let outline = font.outline(font.glyph_id(ch))?;
+    let b = outline.bounds;
+    let (mx, my) = (0.5 * (b.min.x + b.max.x), 0.5 * (b.min.y + b.max.y));
+    let s = 2.0 * r / (b.max.x - b.min.x).max(b.max.y - b.min.y).max(f32::EPSILON);
+    let (sin, cos) = angle.sin_cos();
+    // Font units are y-up.
+    let map = |p: ab_glyph::Point| {
+        let (u, v) = ((p.x - mx) * s, (my - p.y) * s);
+        (c.x + u * cos - v * sin, c.y + u * sin + v * cos)
+    };
+    let mut pb = PathBuilder::new();
+    let mut last: Option<ab_glyph::Point> = None;
+    for curve in &outline.curves {
+        let (start, end) = match curve {
+            OutlineCurve::Line(a, b) => (*a, *b),
+            OutlineCurve::Quad(a, _, b) => (*a, *b),
+            OutlineCurve::Cubic(a, _, _, b) => (*a, *b),
+        };
+        if last != Some(start) {
+            if last.is_some() {
+                pb.close();
+            }
+            let (x, y) = map(start);
+            pb.move_to(x, y);
+        }
+        match curve {
+            OutlineCurve::Line(_, b) => {
+                let (x, y) = map(*b);
+                pb.line_to(x, y);
+            }
+            OutlineCurve::Quad(_, c1, b) => {
+                let ((x1, y1), (x, y)) = (map(*c1), map(*b));
+                pb.quad_to(x1, y1, x, y);
+            }
+            OutlineCurve::Cubic(_, c1, c2, b) => {
+                let ((x1, y1), (x2, y2), (x, y)) = (map(*c1), map(*c2), map(*b));
+                pb.cubic_to(x1, y1, x2, y2, x, y);
+            }
+        }
+        last = Some(end);
+    }
+    pb.close();
+    pb.finish()
+}
+
+/// Sprites are scaled down to this size on load; dots are rarely larger.
+const SPRITE_SIDE: u32 = 256;
+/// How many tinted sprites a render chunk keeps.
+const TINTS: usize = 16;
+
+/// The sprite image at `path`, from the shared cache of decoded images.
+pub fn sprite(path: &str) -> Option<Arc<RgbaImage>> {
+    crate::imgnoise::original(path, SPRITE_SIDE)
+}
+
+/// Tinted copies of a sprite for the most recently used colors. Colors are
+/// bucketed to 32 levels a channel, so the gradual colors along a curve
+/// share tints instead of each tinting the whole sprite.
+#[derive(Default)]
+pub struct Tints(Vec<([u8; 4], Pixmap)>);
+
+impl Tints {
+    /// The sprite tinted by the bucket `color` falls in.
+    pub fn get(&mut self, img: &RgbaImage, color: Color) -> Option<&Pixmap> {
+        let c = color.to_color_u8();
+        let key = [c.red(), c.green(), c.blue(), c.alpha()].map(|v| v >> 3);
+        if let Some(i) = self.0.iter().position(|(k, _)| *k == key) {
+            let entry = self.0.remove(i);
+            self.0.insert(0, entry);
+        } else {
+            let [r, g, b, a] = key.map(|v| (v as u32 * 255 / 31) as u8);
+            let tile = tinted(img, Color::from_rgba8(r, g, b, a))?;
+            self.0.insert(0, (key, tile));
+            self.0.truncate(TINTS);
+        }
+        self.0.first().map(|(_, tile)| tile)
+    }
+}
+
+/// The sprite multiplied by `color`, alpha included, as a premultiplied
+/// pixmap ready to draw.
+fn tinted(img: &RgbaImage, color: Color) -> Option<Pixmap> {
+    let mut pixmap = Pixmap::new(img.width(), img.height())?;
+    let (cr, cg, cb, ca) = (color.red(), color.green(), color.blue(), color.alpha());
+    for (px, src) in pixmap.pixels_mut().iter_mut().zip(img.pixels()) {
+        let a = src[3] as f32 / 255.0 * ca;
+        let channel = |v: u8, k: f32| (v as f32 * k * a).round() as u8;
+        *px = PremultipliedColorU8::from_rgba(
+            channel(src[0], cr),
+            channel(src[1], cg),
+            channel(src[2], cb),
+            (a * 255.0).round() as u8,
+        )
+        .unwrap_or(PremultipliedColorU8::TRANSPARENT);
+    }
+    Some(pixmap)
+}
@@ -0,0 +1,311 @@
+//! Post-processing of the finished image, after the layers are composited:
+//! chromatic aberration, glow, sharpening, a paper texture, a vignette and
+//! a final film grain, in that order. Radii and offsets are in canvas units
+//! and multiplied by the render scale, so previews and prints match.
+
+use crate::background::film_grain;
+use crate::gui::{section, SliderRow};
+use eframe::egui;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use wassily::prelude::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct EffectsControls {
+    /// Red and blue fringe width at the corners, in pixels; 0 is off.
+    pub aberration: f32,
+    /// Strength of the bloom around bright areas; 0 is off.
+    pub glow: f32,
+    pub glow_radius: f32,
+    /// Lightness above which pixels bloom.
+    pub glow_threshold: f32,
+    /// Unsharp mask amount; 0 is off.
+    pub sharpen: f32,
+    pub sharpen_radius: f32,
+    /// Strength of a paper fiber texture laid over the image; 0 is off.
+    pub paper: f32,
+    /// Darkening toward the corners; 0 is off.
+    pub vignette: f32,
+    /// Film grain over everything, apart from any background grain; 0 is off.
+    pub grain: f32,
+    pub grain_size: f32,
+}
+
+impl Default for EffectsControls {
+    fn default() -> Self {
+        Self {
+            aberration: 0.0,
+            glow: 0.0,
+            glow_radius: 10.0,
+            glow_threshold: 0.6,
+            sharpen: 0.0,
+            sharpen_radius: 2.0,
+            paper: 0.0,
+            vignette: 0.0,
+            grain: 0.0,
+            grain_size: 2.0,
+        }
+    }
+}
+
+impl EffectsControls {
+    pub fn ui(&mut self, ui: &mut egui::Ui) {
+        section(ui, "Effects");
+        egui::Grid::new("effects")
+            .spacing((15.0, 10.0))
+            .min_col_width(90.0)
+            .show(ui, |ui| {
+                SliderRow::new("Aberration", &mut self.aberration, 0.0, 0.0..=10.0)
+                    .hover(&["Red and blue fringes toward", "the corners, in pixels."])
+                    .steps(0.1, 1.0)
+                    .decimals(1)
+                    .show(ui);
+                SliderRow::new("Glow", &mut self.glow, 0.0, 0.0..=2.0)
+                    .hover(&["Bloom around the bright", "parts of the image."])
+                    .steps(0.05, 0.1)
+                    .decimals(2)
+                    .show(ui);
+                if self.glow > 0.0 {
+                    SliderRow::new("Glow Radius", &mut self.glow_radius, 10.0, 1.0..=50.0)
+                        .steps(0.5, 5.0)
+                        .decimals(1)
+                        .show(ui);
+                    SliderRow::new("Threshold", &mut self.glow_threshold, 0.6, 0.0..=1.0)
+                        .hover(&["Lightness above which", "pixels bloom."])
+                        .steps(0.05, 0.1)
+                        .decimals(2)
+                        .show(ui);
+                }
+                SliderRow::new("Sharpen", &mut self.sharpen, 0.0, 0.0..=3.0)
+                    .hover(&["Unsharp mask amount."])
+                    .steps(0.05, 0.1)
+                    .decimals(2)
+                    .show(ui);
+                if self.sharpen > 0.0 {
+                    SliderRow::new("Sharpen Radius", &mut self.sharpen_radius, 2.0, 0.5..=10.0)
+                        .steps(0.5, 1.0)
+                        .decimals(1)
+                        .show(ui);
+                }
+                SliderRow::new("Paper", &mut self.paper, 0.0, 0.0..=1.0)
+                    .hover(&["A paper fiber texture", "over the whole image."])
+                    .steps(0.05, 0.1)
+                    .decimals(2)
+                    .show(ui);
+                SliderRow::new("Vignette", &mut self.vignette, 0.0, 0.0..=1.0)
+                    .hover(&["Darken toward the corners."])
+                    .steps(0.05, 0.1)
+                    .decimals(2)
+                    .show(ui);
+                SliderRow::new("Grain", &mut self.grain, 0.0, 0.0..=3.0)
+                    .hover(&["Film grain over the finished", "image, curves included."])
+                    .steps(0.1, 0.5)
+                    .decimals(1)
+                    .show(ui);
+                if self.grain > 0.0 {
+                    SliderRow::new("Grain Size", &mut self.grain_size, 2.0, 0.1..=6.0)
+                        .steps(0.1, 0.5)
+                        .decimals(1)
+                        .show(ui);
+                }
+            });
+    }
+
+    fn is_off(&self) -> bool {
+        self.aberration <= 0.0
+            && self.glow <= 0.0
+            && self.sharpen <= 0.0
+            && self.paper <= 0.0
+            && self.vignette <= 0.0
+            && self.grain <= 0.0
+    }
+
+    /// Run the chain over the canvas; `seed` drives the grain.
+    pub fn apply(&self, canvas: &mut Canvas, seed: u64) {
+        if self.is_off() {
+            return;
+        }
+        let (w, h) = (canvas.pixmap.width() as usize, canvas.pixmap.height() as usize);
+        let scale = canvas.scale;
+        // Premultiplied rgba in [0, 1].
+        let mut img: Vec<[f32; 4]> = canvas
+            .pixmap
+            .pixels()
+            .par_iter()
+            .map(|p| {
+                [
+                    p.red() as f32 / 255.0,
+                    p.green() as f32 / 255.0,
+                    p.blue() as f32 / 255.0,
+                    p.alpha() as f32 / 255.0,
+                ]
+            })
+            .collect();
+        let (cx, cy) = (0.5 * w as f32, 0.5 * h as f32);
+        let half_diag = (cx * cx + cy * cy).sqrt().max(1.0);
+
+        if self.aberration > 0.0 {
+            // Red is pushed out from the center and blue pulled in, by up
+            // to `aberration` at the corners.
+            let src = img.clone();
+            let shift = self.aberration * scale / half_diag;
+            img.par_chunks_mut(w).enumerate().for_each(|(j, row)| {
+                for (i, px) in row.iter_mut().enumerate() {
+                    let (dx, dy) = (i as f32 - cx, j as f32 - cy);
+                    px[0] = sample(&src, w, h, i as f32 - shift * dx, j as f32 - shift * dy)[0];
+                    px[2] = sample(&src, w, h, i as f32 + shift * dx, j as f32 + shift * dy)[2];
+                }
+            });
+        }
+
+        if self.glow > 0.0 {
+            let bright: Vec<[f32; 4]> = img
+                .par_iter()
+                .map(|c| {
+                    let a = c[3].max(f32::EPSILON);
+                    let l = (0.2126 * c[0] + 0.7152 * c[1] + 0.0722 * c[2]) / a;
+                    let k = ((l - self.glow_threshold) / (1.0 - self.glow_threshold).max(0.01))
+                        .clamp(0.0, 1.0);
+                    [c[0] * k, c[1] * k, c[2] * k, c[3] * k]
+                })
+                .collect();
+            let halo = blur(bright, w, h, (self.glow_radius * scale).round() as usize);
+            img.par_iter_mut().zip(&halo).for_each(|(c, g)| {
+                for (v, g) in c.iter_mut().zip(g) {
+                    *v += self.glow * g;
+                }
+            });
+        }
+
+        if self.sharpen > 0.0 {
+            let soft = blur(img.clone(), w, h, (self.sharpen_radius * scale).round() as usize);
+            img.par_iter_mut().zip(&soft).for_each(|(c, s)| {
+                for (v, s) in c.iter_mut().zip(s).take(3) {
+                    *v += self.sharpen * (*v - s);
+                }
+            });
+        }
+
+        if self.paper > 0.0 {
+            // Crossed fibers like the fiber backgrounds, multiplied over the
+            // image, with coordinates in canvas units.
+            let nf = Turbulence::<_, Perlin>::new(Fbm::<Perlin>::default().set_octaves(4))
+                .set_power(2.0)
+                .set_roughness(6);
+            let opts = NoiseOpts::default();
+            img.par_chunks_mut(w).enumerate().for_each(|(j, row)| {
+                let y = j as f32 / scale;
+                for (i, c) in row.iter_mut().enumerate() {
+                    let x = i as f32 / scale;
+                    let t = 0.5 * noise2d_01(&nf, &opts, x * 0.005, y * 0.3)
+                        + 0.5 * noise2d_01(&nf, &opts, x * 0.3 + 17.0, y * 0.005);
+                    let k = 1.0 - 0.3 * self.paper * t;
+                    for v in c.iter_mut().take(3) {
+                        *v *= k;
+                    }
+                }
+            });
+        }
+
+        if self.vignette > 0.0 {
+            img.par_chunks_mut(w).enumerate().for_each(|(j, row)| {
+                for (i, c) in row.iter_mut().enumerate() {
+                    let (dx, dy) = ((i as f32 - cx) / cx.max(1.0), (j as f32 - cy) / cy.max(1.0));
+                    let d = ((dx * dx + dy * dy) / 2.0).sqrt();
+                    let t = ((d - 0.3) / 0.7).clamp(0.0, 1.0);
+                    let k = 1.0 - self.vignette * t * t * (3.0 - 2.0 * t);
+                    for v in c.iter_mut().take(3) {
+                        *v *= k;
+                    }
+                }
+            });
+        }
+
+        if self.grain > 0.0 {
+            let grain = film_grain(w as u32, h as u32, scale, self.grain_size, seed);
+            img.par_iter_mut().zip(&grain).for_each(|(c, g)| {
+                let d = 0.04 * self.grain * g * c[3];
+                for v in c.iter_mut().take(3) {
+                    *v += d;
+                }
+            });
+        }
+
+        canvas
+            .pixmap
+            .pixels_mut()
+            .par_iter_mut()
+            .zip(&img)
+            .for_each(|(px, c)| {
+                let a = c[3].clamp(0.0, 1.0);
+                let q = |v: f32| (v.clamp(0.0, a) * 255.0 + 0.5) as u8;
+                *px = PremultipliedColorU8::from_rgba(q(c[0]), q(c[1]), q(c[2]), q(a))
+                    .unwrap_or(*px);
+            });
+    }
+}
+
+/// The bilinearly interpolated pixel at (x, y), clamped to the image.
+fn sample(img: &[[f32; 4]], w: usize, h: usize, x: f32, y: f32) -> [f32; 4] {
+    let x = x.clamp(0.0, (w - 1) as f32);
+    let y = y.clamp(0.0, (h - 1) as f32);
+    let (i, j) = (x as usize, y as usize);
+    let (i1, j1) = ((i + 1).min(w - 1), (j + 1).min(h - 1));
+    let (tx, ty) = (x - i as f32, y - j as f32);
+    let mut out = [0.0; 4];
+    for (k, v) in out.iter_mut().enumerate() {
+        let top = img[j * w + i][k] * (1.0 - tx) + img[j * w + i1][k] * tx;
+        let bottom = img[j1 * w + i][k] * (1.0 - tx) + img[j1 * w + i1][k] * tx;
+        *v = top * (1.0 - ty) + bottom * ty;
+    }
+    out
+}
+
+/// A near gaussian blur of premultiplied rgba with a standard deviation of
+/// about `radius` pixels: three box passes along the rows, then the columns.
+pub fn blur(mut img: Vec<[f32; 4]>, w: usize, h: usize, radius: usize) -> Vec<[f32; 4]> {
+    if radius == 0 {
+        return img;
+    }
+    let (mut w, mut h) = (w, h);
+    for _ in 0..2 {
+        for _ in 0..3 {
+            box_rows(&mut img, w, radius);
+        }
+        img = transpose(&img, w, h);
+        std::mem::swap(&mut w, &mut h);
+    }
+    img
+}
+
+/// A running-sum box blur of each row, repeating the edge pixels.
+fn box_rows(img: &mut [[f32; 4]], w: usize, r: usize) {
+    let n = (2 * r + 1) as f32;
+    img.par_chunks_mut(w).for_each(|row| {
+        let src = row.to_vec();
+        let at = |k: isize| src[k.clamp(0, w as isize - 1) as usize];
+        let mut sum = [0.0f32; 4];
+        for k in -(r as isize)..=(r as isize) {
+            for (s, v) in sum.iter_mut().zip(at(k)) {
+                *s += v;
+            }
+        }
+        for (i, px) in row.iter_mut().enumerate() {
+            *px = sum.map(|s| s / n);
+            let (add, sub) = (at((i + r + 1) as isize), at(i as isize - r as isize));
+            for ((s, a), b) in sum.iter_mut().zip(add).zip(sub) {
+                *s += a - b;
+            }
+        }
+    });
+}
+
+fn transpose(img: &[[f32; 4]], w: usize, h: usize) -> Vec<[f32; 4]> {
+    let mut out = vec![[0.0; 4]; w * h];
+    out.par_chunks_mut(h).enumerate().for_each(|(i, col)| {
+        for (j, px) in col.iter_mut().enumerate() {
+            *px = img[j * w + i];
+        }
+    });
+    out
+}
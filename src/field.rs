@@ -2,6 +2,92 @@ use std::collections::{HashMap, VecDeque};
 
 use wassily::prelude::*;
 
+use crate::common::Integrator;
+use crate::magnitude::{factor, MagnitudeSource, Modulation};
+use crate::noise::FieldMode;
+use crate::stops::Stops;
+use crate::symmetry::Symmetry;
+
+/// Finite difference step, in canvas units, for the noise gradient.
+const GRAD_EPS: f32 = 1.0;
+/// Loop rule: how many of its most recent points a curve may come close to
+/// without that counting as running into itself.
+const LOOP_SKIP: u32 = 12;
+/// Stall rule: the window of steps over which an end must make headway.
+const STALL_STEPS: usize = 20;
+
+/// A uniform grid of tagged points for fast proximity queries; the query
+/// radius must not exceed the cell size.
+pub struct SpatialGrid {
+    cell: f32,
+    cells: HashMap<(i32, i32), Vec<(Point, u32)>>,
+}
+
+impl SpatialGrid {
+    pub fn new(cell: f32) -> Self {
+        Self {
+            cell: cell.max(1e-3),
+            cells: HashMap::new(),
+        }
+    }
+
+    fn key(&self, p: Point) -> (i32, i32) {
+        (
+            (p.x / self.cell).floor() as i32,
+            (p.y / self.cell).floor() as i32,
+        )
+    }
+
+    pub fn insert(&mut self, p: Point, tag: u32) {
+        let k = self.key(p);
+        self.cells.entry(k).or_default().push((p, tag));
+    }
+
+    /// True if a point whose tag passes `keep` lies within `r` of `p`.
+    pub fn near(&self, p: Point, r: f32, keep: impl Fn(u32) -> bool) -> bool {
+        let (i, j) = self.key(p);
+        for di in -1..=1 {
+            for dj in -1..=1 {
+                if let Some(cell_pts) = self.cells.get(&(i + di, j + dj)) {
+                    if cell_pts
+                        .iter()
+                        .any(|(q, tag)| q.dist2(p) < r * r && keep(*tag))
+                    {
+                        return true;
+                    }
+                }
+            }
+        }
+        false
+    }
+
+    /// The closest point within `r` of `p` whose tag passes `keep`, with its
+    /// tag; `r` should not exceed the cell size.
+    pub fn nearest(&self, p: Point, r: f32, keep: impl Fn(u32) -> bool) -> Option<(Point, u32)> {
+        let (i, j) = self.key(p);
+        let mut best: Option<(f32, Point, u32)> = None;
+        for di in -1..=1 {
+            for dj in -1..=1 {
+                for (q, tag) in self.cells.get(&(i + di, j + dj)).into_iter().flatten() {
+                    let d = q.dist2(p);
+                    if d < r * r && keep(*tag) && best.is_none_or(|b| d < b.0) {
+                        best = Some((d, *q, *tag));
+                    }
+                }
+            }
+        }
+        best.map(|(_, q, tag)| (q, tag))
+    }
+}
+
+/// Per-curve bookkeeping for the termination rules: the curve's own points
+/// for loop detection, and earlier curves to keep clear of.
+struct Trail<'a> {
+    own: Option<SpatialGrid>,
+    count: u32,
+    others: Option<(&'a SpatialGrid, f32)>,
+}
+
 #[derive(Clone, Copy, Debug)]
 pub struct Vertex {
     pub x: f32,
@@ -31,12 +117,244 @@ pub struct Field {
     /// Constant rotation added to every field angle; used to keep curves
     /// perpendicular to a rotated seed column.
     pub angle_offset: f32,
+    /// Polar or symmetric layout of the field; the noise function samples
+    /// the folded points and `field_angle` orients them back.
+    pub symmetry: Symmetry,
+    /// How the flow angle is derived from the noise.
+    pub mode: FieldMode,
+    /// Follow the field backwards.
+    pub reverse: bool,
+    /// Step length, turning speed and stroke width modulation.
+    pub modulation: Modulation,
+    /// The streamline integration scheme.
+    pub integrator: Integrator,
+    /// Adaptive integrator: the largest accepted position error per step,
+    /// in canvas units.
+    pub tolerance: f32,
+    /// Keep the `speed` momentum for the higher-order integrators; without
+    /// it curves follow the field exactly. Euler always uses momentum.
+    pub momentum: bool,
+    /// Rules that end curves early.
+    pub stops: Stops,
 }
 
 impl Field {
+    /// The noise gradient at (x, y) in canvas units, by central differences.
+    pub fn gradient(&self, x: f32, y: f32) -> (f32, f32) {
+        let n = |x, y| noise2d(&self.noise_function, &self.noise_opts, x, y);
+        (
+            (n(x + GRAD_EPS, y) - n(x - GRAD_EPS, y)) / (2.0 * GRAD_EPS),
+            (n(x, y + GRAD_EPS) - n(x, y - GRAD_EPS)) / (2.0 * GRAD_EPS),
+        )
+    }
+
+    /// The flow direction at (x, y), before `angle_offset`.
+    pub fn flow_angle(&self, x: f32, y: f32) -> f32 {
+        match self.mode {
+            FieldMode::Value => {
+                let theta = noise2d(&self.noise_function, &self.noise_opts, x, y) * PI;
+                if self.symmetry.is_off() {
+                    theta
+                } else {
+                    let (_, _, frame) = self.symmetry.fold(x, y);
+                    frame.orient(theta)
+                }
+            }
+            // Derivatives are taken of the already folded field in canvas
+            // space, so they need no reorienting under symmetry.
+            FieldMode::Gradient => {
+                let (gx, gy) = self.gradient(x, y);
+                gy.atan2(gx)
+            }
+            FieldMode::Contour => {
+                let (gx, gy) = self.gradient(x, y);
+                gx.atan2(-gy)
+            }
+        }
+    }
+
+    /// The modulation magnitude at (x, y), in [0, 1].
+    pub fn magnitude(&self, x: f32, y: f32) -> f32 {
+        let m = match self.modulation.source {
+            MagnitudeSource::Noise => noise2d_01(&self.noise_function, &self.noise_opts, x, y),
+            MagnitudeSource::Gradient => {
+                let (gx, gy) = self.gradient(x, y);
+                let g = (gx * gx + gy * gy).sqrt() * self.modulation.grad_norm;
+                g / (1.0 + g)
+            }
+            MagnitudeSource::Image => self
+                .modulation
+                .image
+                .as_ref()
+                .map_or(0.5, |img| 0.5 * img.value(x, y) + 0.5),
+        }
+        .clamp(0.0, 1.0);
+        if self.modulation.invert {
+            1.0 - m
+        } else {
+            m
+        }
+    }
+
+    /// Stroke width multiplier at (x, y); 1 when width modulation is off.
+    pub fn width_factor(&self, x: f32, y: f32) -> f32 {
+        if self.modulation.width > 0.0 {
+            factor(self.magnitude(x, y), self.modulation.width)
+        } else {
+            1.0
+        }
+    }
+
+    /// The step length taken from (x, y).
+    #[inline]
+    fn step(&self, x: f32, y: f32) -> f32 {
+        if self.modulation.step > 0.0 {
+            self.step_size * factor(self.magnitude(x, y), self.modulation.step)
+        } else {
+            self.step_size
+        }
+    }
+
     #[inline]
     fn field_angle(&self, x: f32, y: f32) -> f32 {
-        noise2d(&self.noise_function, &self.noise_opts, x, y) * PI + self.angle_offset
+        let reverse = if self.reverse { PI } else { 0.0 };
+        self.flow_angle(x, y) + self.angle_offset + reverse
+    }
+
+    /// The field angle at (x, y) unwrapped to within half a turn of
+    /// `theta`. Symmetric and derivative fields jump by whole turns (fold
+    /// lines, the atan2 cut), and blending across such a jump would swing
+    /// the curve around.
+    #[inline]
+    fn target(&self, theta: f32, x: f32, y: f32) -> f32 {
+        let mut target = self.field_angle(x, y);
+        if !self.symmetry.is_off() || self.mode != FieldMode::Value {
+            target -= TAU * ((target - theta) / TAU).round();
+        }
+        target
+    }
+
+    /// The turning speed at (x, y).
+    #[inline]
+    fn speed_at(&self, x: f32, y: f32) -> f32 {
+        if self.modulation.speed > 0.0 {
+            (self.speed * factor(self.magnitude(x, y), self.modulation.speed)).min(1.0)
+        } else {
+            self.speed
+        }
+    }
+
+    /// Momentum step: turn `theta` toward the field angle at (x, y) by
+    /// `speed`.
+    #[inline]
+    fn turn(&self, theta: f32, x: f32, y: f32) -> f32 {
+        let speed = self.speed_at(x, y);
+        (1.0 - speed) * theta + speed * self.target(theta, x, y)
+    }
+
+    /// The next vertex after `v`, walking forward or, if `back`, against
+    /// the flow.
+    fn advance(&self, v: Vertex, back: bool) -> Vertex {
+        let h = self.step(v.x, v.y);
+        match self.integrator {
+            // Move along the current heading, then turn toward the field.
+            Integrator::Euler => {
+                let a = if back { PI + v.theta } else { v.theta };
+                let x1 = v.x + h * a.cos();
+                let y1 = v.y + h * a.sin();
+                Vertex::new(x1, y1, self.turn(v.theta, x1, y1))
+            }
+            Integrator::Midpoint | Integrator::Rk4 => self.rk_step(v, h, back),
+            // Step doubling: compare one full step against two half steps
+            // and halve the step until they agree to within `tolerance`.
+            // This only refines: every step starts over from the full step
+            // length, so it shrinks where the flow bends sharply and is
+            // back to full as soon as the flow straightens.
+            Integrator::Adaptive => {
+                let tol = self.tolerance.max(1e-4);
+                let mut h = h;
+                loop {
+                    let full = self.rk_step(v, h, back);
+                    let half = self.rk_step(self.rk_step(v, 0.5 * h, back), 0.5 * h, back);
+                    let err = ((full.x - half.x).powi(2) + (full.y - half.y).powi(2)).sqrt();
+                    if err <= tol || h <= self.step_size / 16.0 {
+                        return half;
+                    }
+                    h *= 0.5;
+                }
+            }
+        }
+    }
+
+    /// Where `p` ends up after `steps` steps along the flow, ignoring the
+    /// termination rules; closed shapes are deformed this way.
+    pub fn advect(&self, p: Point, steps: u32) -> Point {
+        let mut v = Vertex::new(p.x, p.y, self.field_angle(p.x, p.y));
+        for _ in 0..steps {
+            v = self.advance(v, false);
+        }
+        v.to_point()
+    }
+
+    /// The rate of change of (x, y, theta) per unit arc length. With
+    /// momentum the heading relaxes toward the field at `speed` per step,
+    /// the continuous form of the Euler blend; without it curves are
+    /// the field's own streamlines.
+    fn deriv(&self, x: f32, y: f32, theta: f32, back: bool) -> (f32, f32, f32) {
+        let sign = if back { -1.0 } else { 1.0 };
+        if self.momentum {
+            let rate = self.speed_at(x, y) / self.step_size.max(1e-3);
+            (
+                sign * theta.cos(),
+                sign * theta.sin(),
+                rate * (self.target(theta, x, y) - theta),
+            )
+        } else {
+            let a = self.target(theta, x, y);
+            (sign * a.cos(), sign * a.sin(), 0.0)
+        }
+    }
+
+    /// One explicit Runge-Kutta step of length `h`: the midpoint method or
+    /// classic RK4, as chosen by `integrator`.
+    fn rk_step(&self, v: Vertex, h: f32, back: bool) -> Vertex {
+        let (x, y, t) = (v.x, v.y, v.theta);
+        let k1 = self.deriv(x, y, t, back);
+        let (dx, dy, dt) = if self.integrator == Integrator::Midpoint {
+            self.deriv(
+                x + 0.5 * h * k1.0,
+                y + 0.5 * h * k1.1,
+                t + 0.5 * h * k1.2,
+                back,
+            )
+        } else {
+            let k2 = self.deriv(
+                x + 0.5 * h * k1.0,
+                y + 0.5 * h * k1.1,
+                t + 0.5 * h * k1.2,
+                back,
+            );
+            let k3 = self.deriv(
+                x + 0.5 * h * k2.0,
+                y + 0.5 * h * k2.1,
+                t + 0.5 * h * k2.2,
+                back,
+            );
+            let k4 = self.deriv(x + h * k3.0, y + h * k3.1, t + h * k3.2, back);
+            (
+                (k1.0 + 2.0 * k2.0 + 2.0 * k3.0 + k4.0) / 6.0,
+                (k1.1 + 2.0 * k2.1 + 2.0 * k3.1 + k4.1) / 6.0,
+                (k1.2 + 2.0 * k2.2 + 2.0 * k3.2 + k4.2) / 6.0,
+            )
+        };
+        let (x1, y1) = (x + h * dx, y + h * dy);
+        // Without momentum the heading is just the field at the new point.
+        let theta = if self.momentum {
+            t + h * dt
+        } else {
+            self.target(t, x1, y1)
+        };
+        Vertex::new(x1, y1, theta)
     }
 
     fn outside(&self, x: f32, y: f32) -> bool {
@@ -44,67 +362,181 @@ impl Field {
         x < -m || x > self.width as f32 + m || y < -m || y > self.height as f32 + m
     }
 
+    /// True if the walk must end rather than step to `q`; `lag` is the
+    /// point `STALL_STEPS` back along the same end, if there is one.
+    fn blocked(&self, q: Point, lag: Option<Point>, trail: &Trail) -> bool {
+        if !self
+            .stops
+            .inside(q.x, q.y, self.width as f32, self.height as f32)
+        {
+            return true;
+        }
+        if let Some((others, gap)) = trail.others {
+            if others.near(q, gap, |_| true) {
+                return true;
+            }
+        }
+        if let Some(own) = &trail.own {
+            if own.near(q, 0.75 * self.step_size, |tag| tag + LOOP_SKIP < trail.count) {
+                return true;
+            }
+        }
+        if self.stops.stall {
+            if let Some(lag) = lag {
+                if lag.dist2(q) < (2.0 * self.step_size).powi(2) {
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
+    fn record(trail: &mut Trail, q: Point) {
+        if let Some(own) = &mut trail.own {
+            own.insert(q, trail.count);
+        }
+        trail.count += 1;
+    }
+
+    /// Add one step at the back of the curve, or the front if `front`,
+    /// unless a termination rule ends it there. Returns whether that end is
+    /// still open.
+    fn grow(&self, vertices: &mut VecDeque<Vertex>, front: bool, trail: &mut Trail) -> bool {
+        let n = vertices.len();
+        let (v, lag) = if front {
+            (
+                vertices[0],
+                (n > STALL_STEPS).then(|| vertices[STALL_STEPS].to_point()),
+            )
+        } else {
+            (
+                vertices[n - 1],
+                (n > STALL_STEPS).then(|| vertices[n - 1 - STALL_STEPS].to_point()),
+            )
+        };
+        let next = self.advance(v, front);
+        let q = next.to_point();
+        if self.blocked(q, lag, trail) {
+            return false;
+        }
+        Self::record(trail, q);
+        if front {
+            vertices.push_front(next);
+        } else {
+            vertices.push_back(next);
+        }
+        true
+    }
+
     /// Walk both ends of a curve onward through the flow field until they
     /// leave the canvas (plus a margin), so no curve endpoint is visible in
     /// the piece. Capped, since a vortex in the field can trap an end forever.
-    fn extend_ends(&self, vertices: &mut VecDeque<Vertex>) {
+    fn extend_ends(&self, vertices: &mut VecDeque<Vertex>, trail: &mut Trail) {
         if !self.hide_ends {
             return;
         }
         let cap = (2.0 * (self.width + self.height) as f32 / self.step_size) as u32;
-        let mut theta = vertices.back().unwrap().theta;
         for _ in 0..cap {
             let v = *vertices.back().unwrap();
-            if self.outside(v.x, v.y) {
+            if self.outside(v.x, v.y) || !self.grow(vertices, false, trail) {
                 break;
             }
-            let x1 = v.x + self.step_size * v.theta.cos();
-            let y1 = v.y + self.step_size * v.theta.sin();
-            theta = (1.0 - self.speed) * theta
-                + self.speed * self.field_angle(x1, y1);
-            vertices.push_back(Vertex::new(x1, y1, theta));
         }
-        let mut theta = vertices.front().unwrap().theta;
         for _ in 0..cap {
             let v = *vertices.front().unwrap();
-            if self.outside(v.x, v.y) {
+            if self.outside(v.x, v.y) || !self.grow(vertices, true, trail) {
                 break;
             }
-            let x1 = v.x + self.step_size * (PI + v.theta).cos();
-            let y1 = v.y + self.step_size * (PI + v.theta).sin();
-            theta = (1.0 - self.speed) * theta
-                + self.speed * self.field_angle(x1, y1);
-            vertices.push_front(Vertex::new(x1, y1, theta));
         }
     }
 
+    /// Grow a curve from (x, y): `curve_length` steps forward, or half that
+    /// each way if `two_sided`, either end stopping early where the
+    /// termination rules say so. `others` holds earlier curves to keep
+    /// `gap` away from. Empty if the seed itself is ruled out.
+    fn walk(
+        &self,
+        x: f32,
+        y: f32,
+        two_sided: bool,
+        others: Option<(&SpatialGrid, f32)>,
+    ) -> Vec<Point> {
+        let mut trail = Trail {
+            own: self.stops.loops.then(|| SpatialGrid::new(self.step_size)),
+            count: 0,
+            others,
+        };
+        let seed = pt(x, y);
+        if self.blocked(seed, None, &trail) {
+            return Vec::new();
+        }
+        Self::record(&mut trail, seed);
+        let mut vertices: VecDeque<Vertex> = VecDeque::new();
+        vertices.push_back(Vertex::new(x, y, self.field_angle(x, y)));
+        let steps = if two_sided {
+            self.curve_length / 2
+        } else {
+            self.curve_length
+        };
+        let (mut back_open, mut front_open) = (true, two_sided);
+        for _ in 0..steps {
+            if back_open {
+                back_open = self.grow(&mut vertices, false, &mut trail);
+            }
+            if front_open {
+                front_open = self.grow(&mut vertices, true, &mut trail);
+            }
+            if !back_open && !front_open {
+                break;
+            }
+        }
+        self.extend_ends(&mut vertices, &mut trail);
+        vertices.into_iter().map(|v| v.to_point()).collect()
+    }
+
+    /// Curves from each start in turn, each ending where it would come
+    /// within `gap` of an earlier one; seeds already that close are
+    /// dropped. Sequential, since every curve depends on all before it.
+    /// Returns each curve with its seed point.
+    pub fn curves_avoiding(
+        &self,
+        starts: &[Point],
+        gap: f32,
+        two_sided: bool,
+    ) -> Vec<(Point, Vec<Point>)> {
+        let mut grid = SpatialGrid::new(gap);
+        let mut out = Vec::new();
+        for &p in starts {
+            let pts = self.walk(p.x, p.y, two_sided, Some((&grid, gap)));
+            if pts.is_empty() {
+                continue;
+            }
+            for q in &pts {
+                grid.insert(*q, 0);
+            }
+            out.push((p, pts));
+        }
+        out
+    }
+
     /// Jobard-Lefer evenly spaced streamlines: no two curves come closer
-    /// than half of `sep`, and new curves are seeded `sep` away from the
-    /// existing ones until the canvas is saturated. Returns each curve with
-    /// its seed point. `curve_length` caps the steps per direction and the
-    /// usual momentum smoothing (`speed`) applies.
-    pub fn evenly_spaced(&self, sep: f32, two_sided: bool) -> Vec<(Point, Vec<Point>)> {
+    /// than half of the local separation `sep_at`, and new curves are seeded
+    /// one local separation away from the existing ones until the canvas is
+    /// saturated. `sep` is the largest separation `sep_at` returns. Returns
+    /// each curve with its seed point. `curve_length` caps the steps per
+    /// direction and the usual momentum smoothing (`speed`) applies.
+    pub fn evenly_spaced(
+        &self,
+        sep: f32,
+        sep_at: &dyn Fn(Point) -> f32,
+        two_sided: bool,
+    ) -> Vec<(Point, Vec<Point>)> {
         let sep = sep.max(2.0);
-        let d_test = 0.5 * sep;
+        let sep_at = |p: Point| sep_at(p).clamp(1.0, sep);
         let w = self.width as f32;
         let h = self.height as f32;
         let margin = 0.05 * w.max(h);
-        let cell = sep;
-        let key = |p: Point| ((p.x / cell).floor() as i32, (p.y / cell).floor() as i32);
-        let mut grid: HashMap<(i32, i32), Vec<Point>> = HashMap::new();
-        let near = |grid: &HashMap<(i32, i32), Vec<Point>>, p: Point, r: f32| -> bool {
-            let (i, j) = key(p);
-            for di in -1..=1 {
-                for dj in -1..=1 {
-                    if let Some(cell_pts) = grid.get(&(i + di, j + dj)) {
-                        if cell_pts.iter().any(|q| q.dist2(p) < r * r) {
-                            return true;
-                        }
-                    }
-                }
-            }
-            false
-        };
+        let mut grid = SpatialGrid::new(sep);
         let in_bounds =
             |p: Point| p.x > -margin && p.x < w + margin && p.y > -margin && p.y < h + margin;
         let cap = self.curve_length.max(1) as usize;
@@ -113,50 +545,51 @@ impl Field {
         let mut out: Vec<(Point, Vec<Point>)> = Vec::new();
         let mut queue: VecDeque<Point> = VecDeque::from([pt(w / 2.0, h / 2.0)]);
         while let Some(seed) = queue.pop_front() {
-            if !in_bounds(seed) || near(&grid, seed, sep) {
+            let seed_sep = sep_at(seed);
+            if !in_bounds(seed) || grid.near(seed, seed_sep, |_| true) {
                 continue;
             }
             let mut pts: VecDeque<Point> = VecDeque::new();
             pts.push_back(seed);
             let theta0 = angle_at(seed);
-            for heading in [0.0, PI] {
-                if heading > 0.0 && !two_sided {
+            for back in [false, true] {
+                if back && !two_sided {
                     break;
                 }
-                let mut theta = theta0;
-                let mut p = seed;
+                let mut v = Vertex::new(seed.x, seed.y, theta0);
                 for _ in 0..cap {
-                    let q = pt(
-                        p.x + self.step_size * (heading + theta).cos(),
-                        p.y + self.step_size * (heading + theta).sin(),
-                    );
-                    if !in_bounds(q) || near(&grid, q, d_test) {
+                    let next = self.advance(v, back);
+                    let q = next.to_point();
+                    if !in_bounds(q) || grid.near(q, 0.5 * sep_at(q), |_| true) {
                         break;
                     }
-                    theta = (1.0 - self.speed) * theta + self.speed * angle_at(q);
-                    if heading == 0.0 {
-                        pts.push_back(q);
-                    } else {
+                    if back {
                         pts.push_front(q);
+                    } else {
+                        pts.push_back(q);
                     }
-                    p = q;
+                    v = next;
                 }
             }
             // discard stubs shorter than one separation
-            if (pts.len() as f32) * self.step_size < sep {
+            if (pts.len() as f32) * self.step_size < seed_sep {
                 continue;
             }
             let pts: Vec<Point> = pts.into();
             for p in &pts {
-                grid.entry(key(*p)).or_default().push(*p);
+                grid.insert(*p, 0);
             }
-            // candidate seeds one separation off each side, every ~sep of arc
-            let every = ((sep / self.step_size).ceil() as usize).max(1);
-            for p in pts.iter().step_by(every) {
-                let a = angle_at(*p);
+            // candidate seeds one local separation off each side, every
+            // ~separation of arc
+            let mut i = 0;
+            while i < pts.len() {
+                let p = pts[i];
+                let d = sep_at(p);
+                let a = angle_at(p);
                 let (nx, ny) = (-a.sin(), a.cos());
-                queue.push_back(pt(p.x + sep * nx, p.y + sep * ny));
-                queue.push_back(pt(p.x - sep * nx, p.y - sep * ny));
+                queue.push_back(pt(p.x + d * nx, p.y + d * ny));
+                queue.push_back(pt(p.x - d * nx, p.y - d * ny));
+                i += ((d / self.step_size).ceil() as usize).max(1);
             }
             out.push((seed, pts));
         }
@@ -164,52 +597,104 @@ impl Field {
     }
 
     pub fn curve1(&self, x: f32, y: f32) -> Vec<Point> {
-        let mut vertices: VecDeque<Vertex> = VecDeque::new();
-        let mut theta = self.field_angle(x, y);
-        vertices.push_back(Vertex::new(x, y, theta));
-        for _ in 0..self.curve_length {
-            let v = *vertices.back().unwrap();
-            let x1 = v.x + self.step_size * v.theta.cos();
-            let y1 = v.y + self.step_size * v.theta.sin();
-            theta = (1.0 - self.speed) * theta
-                + self.speed * self.field_angle(x1, y1);
-            vertices.push_back(Vertex::new(x1, y1, theta));
-        }
-        self.extend_ends(&mut vertices);
-        vertices.into_iter().map(|v| v.to_point()).collect()
+        self.walk(x, y, false, None)
     }
 
     pub fn curve2(&self, x: f32, y: f32) -> Vec<Point> {
-        let mut vertices: VecDeque<Vertex> = VecDeque::new();
-        let mut theta_back = self.field_angle(x, y);
-        let mut theta_front = theta_back;
-        let v = Vertex::new(x, y, theta_back);
-        vertices.push_back(v);
-        let mut v_back: Vertex;
-        let mut v_front: Vertex;
-        let mut x_back1: f32;
-        let mut y_back1: f32;
-        let mut x_front1: f32;
-        let mut y_front1: f32;
-        let mut v1: Vertex;
-        let mut v2: Vertex;
-        for _ in 0..self.curve_length / 2 {
-            v_back = *vertices.back().unwrap();
-            v_front = *vertices.front().unwrap();
-            x_back1 = v_back.x + self.step_size * v_back.theta.cos();
-            y_back1 = v_back.y + self.step_size * v_back.theta.sin();
-            x_front1 = v_front.x + self.step_size * (PI + v_front.theta).cos();
-            y_front1 = v_front.y + self.step_size * (PI + v_front.theta).sin();
-            theta_back = (1.0 - self.speed) * theta_back
-                + self.speed * self.field_angle(x_back1, y_back1);
-            theta_front = (1.0 - self.speed) * theta_front
-                + self.speed * self.field_angle(x_front1, y_front1);
-            v1 = Vertex::new(x_back1, y_back1, theta_back);
-            v2 = Vertex::new(x_front1, y_front1, theta_front);
-            vertices.push_back(v1);
-            vertices.push_front(v2);
-        }
-        self.extend_ends(&mut vertices);
-        vertices.into_iter().map(|v| v.to_point()).collect()
+        self.walk(x, y, true, None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::noise::Sinusoidal;
+    use crate::symmetry::SymmetryControls;
+
+    const SIZE: u32 = 1000;
+
+    /// A contour field over `noise`. Contours follow the level sets of the
+    /// noise, so the noise value is constant along every exact streamline.
+    fn contour_field(noise: Box<dyn NoiseFn<f64, 2>>, step: f32, integrator: Integrator) -> Field {
+        Field {
+            noise_function: noise,
+            noise_opts: NoiseOpts::with_wh(SIZE, SIZE).scales(1.0),
+            step_size: step,
+            width: SIZE,
+            height: SIZE,
+            curve_length: 100,
+            speed: 1.0,
+            hide_ends: false,
+            angle_offset: 0.0,
+            symmetry: Symmetry::new(&SymmetryControls::default(), SIZE, SIZE),
+            mode: FieldMode::Contour,
+            reverse: false,
+            modulation: Modulation::off(),
+            integrator,
+            tolerance: 1e-3,
+            momentum: false,
+            stops: Stops::off(),
+        }
+    }
+
+    /// Concentric rings about the origin; the streamlines are circles.
+    fn cylinders() -> Box<dyn NoiseFn<f64, 2>> {
+        Box::new(Cylinders::default())
+    }
+
+    /// Equal frequencies on both axes; the streamlines are the closed
+    /// curves sin(kx) + sin(ky) = c.
+    fn sinusoidal() -> Box<dyn NoiseFn<f64, 2>> {
+        let k = 5.0 * std::f64::consts::PI;
+        Box::new(Sinusoidal::new(k, k, 1.0, 1.0))
+    }
+
+    /// How far off its streamline a curve from `start` ends after `steps`
+    /// steps, in canvas units: the drift of the noise value over the
+    /// gradient at the start.
+    fn drift(field: &Field, start: Point, steps: u32) -> f32 {
+        let n = |p: Point| noise2d(&field.noise_function, &field.noise_opts, p.x, p.y);
+        let (gx, gy) = field.gradient(start.x, start.y);
+        let mut v = Vertex::new(start.x, start.y, field.field_angle(start.x, start.y));
+        for _ in 0..steps {
+            v = field.advance(v, false);
+        }
+        (n(v.to_point()) - n(start)).abs() / (gx * gx + gy * gy).sqrt()
+    }
+
+    fn errors(noise: fn() -> Box<dyn NoiseFn<f64, 2>>, start: Point) -> [f32; 3] {
+        [Integrator::Euler, Integrator::Midpoint, Integrator::Rk4]
+            .map(|integrator| drift(&contour_field(noise(), 8.0, integrator), start, 60))
+    }
+
+    #[test]
+    fn higher_order_integrators_stay_closer_to_cylinder_streamlines() {
+        let [euler, midpoint, rk4] = errors(cylinders, pt(40.0, 0.0));
+        assert!(euler > midpoint, "euler {euler} midpoint {midpoint}");
+        assert!(midpoint > rk4, "midpoint {midpoint} rk4 {rk4}");
+    }
+
+    #[test]
+    fn higher_order_integrators_stay_closer_to_sinusoidal_streamlines() {
+        let [euler, midpoint, rk4] = errors(sinusoidal, pt(160.0, 100.0));
+        assert!(euler > midpoint, "euler {euler} midpoint {midpoint}");
+        assert!(midpoint > rk4, "midpoint {midpoint} rk4 {rk4}");
+    }
+
+    #[test]
+    fn adaptive_stays_within_tolerance() {
+        // Steps this long against curves this tight make the step halve.
+        for (noise, start) in [
+            (cylinders as fn() -> Box<dyn NoiseFn<f64, 2>>, pt(40.0, 0.0)),
+            (sinusoidal, pt(160.0, 100.0)),
+        ] {
+            let field = contour_field(noise(), 30.0, Integrator::Adaptive);
+            let steps = 16;
+            let error = drift(&field, start, steps);
+            assert!(
+                error <= steps as f32 * field.tolerance,
+                "error {error} over {steps} steps"
+            );
+        }
     }
 }
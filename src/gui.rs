@@ -305,6 +305,23 @@ pub fn action_button(ui: &mut egui::Ui, label: &str, enabled: bool, hover: &[&st
     clicked
 }
 
+/// The gray and white checks shown behind transparent images.
+pub fn checkerboard(painter: &egui::Painter, rect: egui::Rect) {
+    const CHECK: f32 = 8.0;
+    painter.rect_filled(rect, 0.0, Color32::WHITE);
+    let (nx, ny) = (
+        (rect.width() / CHECK).ceil() as usize,
+        (rect.height() / CHECK).ceil() as usize,
+    );
+    for j in 0..ny {
+        for i in (j % 2..nx).step_by(2) {
+            let min = rect.min + egui::vec2(i as f32, j as f32) * CHECK;
+            let check = egui::Rect::from_min_size(min, egui::Vec2::splat(CHECK)).intersect(rect);
+            painter.rect_filled(check, 0.0, Color32::from_gray(204));
+        }
+    }
+}
+
 /// A section break: separator plus a centered bold title.
 pub fn section(ui: &mut egui::Ui, title: &str) {
     ui.add_space(SPACE);
@@ -0,0 +1,238 @@
+//! Palettes generated from a base color by a color harmony rule. The hues
+//! sit at the rule's angles around the Oklch hue circle, and a seed deals out
+//! lightness and jitters chroma within the chosen spreads. Palettes worth
+//! keeping are saved by name as user palettes.
+
+use crate::color::to_color32;
+use crate::gui::{color_picker, pick_list, section, SliderRow, SPACE};
+use eframe::egui;
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+use wassily::prelude::palette::{IntoColor, LinSrgb, Oklch};
+use wassily::prelude::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Harmony {
+    /// The base hue and its opposite.
+    Complementary,
+    /// Three hues a third of the circle apart.
+    Triadic,
+    /// The base hue and its neighbors.
+    Analogous,
+    /// The base hue and the two on either side of its opposite.
+    SplitComplementary,
+    /// Four hues a quarter of the circle apart.
+    Tetradic,
+    /// The base hue alone.
+    Monochrome,
+}
+
+impl std::fmt::Display for Harmony {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Harmony::Complementary => "Complementary",
+                Harmony::Triadic => "Triadic",
+                Harmony::Analogous => "Analogous",
+                Harmony::SplitComplementary => "Split Complementary",
+                Harmony::Tetradic => "Tetradic",
+                Harmony::Monochrome => "Monochrome",
+            }
+        )
+    }
+}
+
+impl Harmony {
+    /// The rule's hues, in degrees from the base hue.
+    pub fn offsets(self) -> &'static [f32] {
+        match self {
+            Harmony::Complementary => &[0.0, 180.0],
+            Harmony::Triadic => &[0.0, 120.0, 240.0],
+            Harmony::Analogous => &[-30.0, 0.0, 30.0],
+            Harmony::SplitComplementary => &[0.0, 150.0, 210.0],
+            Harmony::Tetradic => &[0.0, 90.0, 180.0, 270.0],
+            Harmony::Monochrome => &[0.0],
+        }
+    }
+}
+
+/// A named list of colors saved from the generator.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct UserPalette {
+    pub name: String,
+    pub colors: Vec<egui::Color32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct HarmonyControls {
+    pub base: egui::Color32,
+    pub rule: Option<Harmony>,
+    /// How many colors the palette holds.
+    pub count: u32,
+    /// How far lightness strays from the base color's, in Oklch lightness.
+    pub lightness_spread: f32,
+    /// How far chroma strays from the base color's, as a fraction of it.
+    pub chroma_spread: f32,
+    /// Reshuffles the lightness and jitter of the colors.
+    pub seed: u64,
+}
+
+impl Default for HarmonyControls {
+    fn default() -> Self {
+        Self {
+            base: egui::Color32::from_rgb(20, 134, 187),
+            rule: Some(Harmony::SplitComplementary),
+            count: 6,
+            lightness_spread: 0.2,
+            chroma_spread: 0.3,
+            seed: 0,
+        }
+    }
+}
+
+/// The largest chroma at or below `chroma` that keeps the color inside the
+/// sRGB gamut.
+fn in_gamut(l: f32, chroma: f32, hue: f32) -> Oklch {
+    let mut c = chroma;
+    loop {
+        let color = Oklch::new(l, c, hue);
+        let rgb: LinSrgb = color.into_color();
+        let inside = [rgb.red, rgb.green, rgb.blue]
+            .iter()
+            .all(|v| (-1e-4..=1.0 + 1e-4).contains(v));
+        if inside || c < 1e-3 {
+            return color;
+        }
+        c *= 0.95;
+    }
+}
+
+impl HarmonyControls {
+    /// The palette the rule generates from the base color: the rule's hues
+    /// in turn, with lightness spread evenly over the range and dealt out
+    /// by the seed, and chroma jittered around the base color's.
+    pub fn colors(&self) -> Vec<Color> {
+        let (r, g, b) = (self.base.r(), self.base.g(), self.base.b());
+        let base: Oklch =
+            Srgb::new(r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0).into_color();
+        let offsets = self.rule.unwrap_or(Harmony::Complementary).offsets();
+        let n = self.count.max(1) as usize;
+        let mut rng = SmallRng::seed_from_u64(self.seed);
+        let mut lightness: Vec<f32> = (0..n)
+            .map(|k| {
+                let t = k as f32 / (n - 1).max(1) as f32;
+                (base.l + (2.0 * t - 1.0) * self.lightness_spread).clamp(0.05, 0.98)
+            })
+            .collect();
+        lightness.shuffle(&mut rng);
+        lightness
+            .into_iter()
+            .enumerate()
+            .map(|(k, l)| {
+                // A few degrees off the exact angle, so repeated hues are
+                // not identical.
+                let hue = base.hue.into_degrees()
+                    + offsets[k % offsets.len()]
+                    + rng.random_range(-4.0..=4.0f32);
+                let chroma = base.chroma
+                    * (1.0 + rng.random_range(-1.0..=1.0f32) * self.chroma_spread).max(0.0);
+                let rgb: Srgb = in_gamut(l, chroma, hue).into_color();
+                Color::from_rgba(
+                    rgb.red.clamp(0.0, 1.0),
+                    rgb.green.clamp(0.0, 1.0),
+                    rgb.blue.clamp(0.0, 1.0),
+                    1.0,
+                )
+                .unwrap_or(*WHITE)
+            })
+            .collect()
+    }
+
+    /// The generator rows, a swatch strip of the result, and a button to
+    /// save it by name into `library`.
+    pub fn ui(&mut self, ui: &mut egui::Ui, library: &mut Vec<UserPalette>) {
+        use Harmony::*;
+        section(ui, "Harmony");
+        egui::Grid::new("harmony")
+            .spacing((15.0, 10.0))
+            .min_col_width(90.0)
+            .show(ui, |ui| {
+                color_picker(ui, "Base Color", &mut self.base);
+                pick_list(
+                    ui,
+                    "Rule",
+                    &[
+                        Complementary,
+                        Triadic,
+                        Analogous,
+                        SplitComplementary,
+                        Tetradic,
+                        Monochrome,
+                    ],
+                    &mut self.rule,
+                );
+                SliderRow::new("Colors", &mut self.count, 6, 2..=12)
+                    .steps(1.0, 2.0)
+                    .show(ui);
+                SliderRow::new("Lightness", &mut self.lightness_spread, 0.2, 0.0..=0.5)
+                    .hover(&["How far lightness strays", "from the base color."])
+                    .steps(0.01, 0.05)
+                    .decimals(2)
+                    .show(ui);
+                SliderRow::new("Chroma", &mut self.chroma_spread, 0.3, 0.0..=1.0)
+                    .hover(&["How far chroma strays", "from the base color."])
+                    .steps(0.05, 0.1)
+                    .decimals(2)
+                    .show(ui);
+                ui.label("Seed").on_hover_ui(|ui| {
+                    ui.colored_label(egui::Color32::ORANGE, "Deals out the lightness and");
+                    ui.colored_label(egui::Color32::ORANGE, "jitter; Shuffle picks a new one.");
+                });
+                ui.horizontal(|ui| {
+                    ui.add(egui::DragValue::new(&mut self.seed));
+                    if ui.button("Shuffle").clicked() {
+                        self.seed = rand::random::<u64>() >> 16;
+                    }
+                });
+                ui.end_row();
+            });
+
+        ui.add_space(SPACE);
+        let colors = self.colors();
+        let (strip, _) =
+            ui.allocate_exact_size(egui::vec2(ui.available_width(), 20.0), egui::Sense::hover());
+        let w = strip.width() / colors.len() as f32;
+        for (k, c) in colors.iter().enumerate() {
+            let swatch = egui::Rect::from_min_size(
+                strip.min + egui::vec2(k as f32 * w, 0.0),
+                egui::vec2(w, strip.height()),
+            );
+            ui.painter().rect_filled(swatch, 0.0, to_color32(*c));
+        }
+
+        ui.add_space(SPACE);
+        let id = ui.id().with("palette name");
+        let mut name: String = ui.data(|d| d.get_temp(id)).unwrap_or_default();
+        ui.horizontal(|ui| {
+            ui.add(egui::TextEdit::singleline(&mut name).desired_width(150.0));
+            if ui
+                .add_enabled(!name.trim().is_empty(), egui::Button::new("Save Palette"))
+                .on_hover_text("Keep these colors as a user palette.")
+                .clicked()
+            {
+                let palette = UserPalette {
+                    name: name.trim().to_string(),
+                    colors: colors.iter().map(|c| to_color32(*c)).collect(),
+                };
+                // Saving under an existing name replaces that palette.
+                match library.iter_mut().find(|p| p.name == palette.name) {
+                    Some(p) => *p = palette,
+                    None => library.push(palette),
+                }
+            }
+        });
+        ui.data_mut(|d| d.insert_temp(id, name));
+    }
+}
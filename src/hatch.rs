@@ -0,0 +1,151 @@
+//! The Hatch style: short strokes laid across each curve at regular
+//! intervals, perpendicular to the flow or at a fixed angle, with an
+//! optional second pass for cross-hatching. Closer intervals where a
+//! density map is strong give engraving-like tone, and the output is plain
+//! line segments, so it plots well.
+
+use crate::density::DensityControls;
+use crate::gui::{pick_list, section, SliderRow};
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+use wassily::prelude::*;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum HatchAngle {
+    /// Across the local flow direction.
+    Perpendicular,
+    /// The same angle everywhere.
+    Fixed,
+}
+
+impl std::fmt::Display for HatchAngle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                HatchAngle::Perpendicular => "Perpendicular",
+                HatchAngle::Fixed => "Fixed",
+            }
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct HatchControls {
+    pub angle_mode: Option<HatchAngle>,
+    /// Fixed: the stroke angle in degrees, 0 is horizontal.
+    pub angle: f32,
+    /// Distance between strokes along the curve, in pixels.
+    pub interval: f32,
+    /// Length of each stroke, in pixels.
+    pub length: f32,
+    /// Draw a second pass turned by `cross_angle`.
+    pub cross: bool,
+    pub cross_angle: f32,
+    /// Shrinks the interval where a scalar map is strong.
+    pub density: DensityControls,
+}
+
+impl Default for HatchControls {
+    fn default() -> Self {
+        Self {
+            angle_mode: Some(HatchAngle::Perpendicular),
+            angle: 45.0,
+            interval: 6.0,
+            length: 12.0,
+            cross: false,
+            cross_angle: 90.0,
+            density: DensityControls::default(),
+        }
+    }
+}
+
+impl HatchControls {
+    pub fn ui(&mut self, ui: &mut egui::Ui) {
+        use HatchAngle::*;
+        section(ui, "Hatch");
+        egui::Grid::new("hatch")
+            .spacing((15.0, 10.0))
+            .min_col_width(90.0)
+            .show(ui, |ui| {
+                pick_list(ui, "Angle", &[Perpendicular, Fixed], &mut self.angle_mode);
+                if self.angle_mode == Some(Fixed) {
+                    SliderRow::new("Degrees", &mut self.angle, 45.0, 0.0..=180.0)
+                        .hover(&["Stroke angle;", "0 is horizontal."])
+                        .steps(1.0, 15.0)
+                        .show(ui);
+                }
+                SliderRow::new("Interval", &mut self.interval, 6.0, 1.0..=50.0)
+                    .hover(&["Pixels between strokes", "along the curve."])
+                    .steps(0.5, 2.0)
+                    .decimals(1)
+                    .show(ui);
+                SliderRow::new("Length", &mut self.length, 12.0, 1.0..=100.0)
+                    .hover(&["Length of each stroke", "in pixels."])
+                    .steps(0.5, 5.0)
+                    .decimals(1)
+                    .show(ui);
+                ui.label("Cross").on_hover_ui(|ui| {
+                    ui.colored_label(egui::Color32::ORANGE, "Add a second pass of strokes");
+                    ui.colored_label(egui::Color32::ORANGE, "at another angle.");
+                });
+                ui.checkbox(&mut self.cross, "");
+                ui.end_row();
+                if self.cross {
+                    SliderRow::new("Cross Angle", &mut self.cross_angle, 90.0, 0.0..=180.0)
+                        .hover(&["Turn of the second pass", "from the first, degrees."])
+                        .steps(1.0, 15.0)
+                        .show(ui);
+                }
+            });
+        self.density.ui(ui, "Hatch Density");
+    }
+
+    /// The hatch strokes along `pts` as segment end points paired with the
+    /// index of the curve point each starts from. `interval_at` gives the
+    /// spacing near a point; segments longer than `max_jump` get no marks.
+    pub fn strokes(
+        &self,
+        pts: &[Point],
+        max_jump: f32,
+        interval_at: &dyn Fn(Point) -> f32,
+    ) -> Vec<(Point, Point, usize)> {
+        let mut out = Vec::new();
+        let half = 0.5 * self.length;
+        let mut angles = vec![0.0];
+        if self.cross {
+            angles.push(self.cross_angle.to_radians());
+        }
+        let mut need = 0.0;
+        for i in 0..pts.len().saturating_sub(1) {
+            let (a, b) = (pts[i], pts[i + 1]);
+            let seg = a.dist2(b).sqrt();
+            if seg > max_jump {
+                need = 0.0;
+                continue;
+            }
+            let mut s = 0.0;
+            while seg - s >= need {
+                s += need;
+                let t = if seg > 0.0 { s / seg } else { 0.0 };
+                let p = pt(a.x + (b.x - a.x) * t, a.y + (b.y - a.y) * t);
+                let base = match self.angle_mode {
+                    Some(HatchAngle::Fixed) => self.angle.to_radians(),
+                    _ => (b.y - a.y).atan2(b.x - a.x) + PI / 2.0,
+                };
+                for turn in &angles {
+                    let (sin, cos) = (base + turn).sin_cos();
+                    out.push((
+                        pt(p.x - half * cos, p.y - half * sin),
+                        pt(p.x + half * cos, p.y + half * sin),
+                        i,
+                    ));
+                }
+                need = interval_at(p).max(0.5);
+            }
+            need -= seg - s;
+        }
+        out
+    }
+}
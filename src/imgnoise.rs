@@ -8,7 +8,7 @@ use image::RgbaImage;
 use serde::{Deserialize, Serialize};
 use std::sync::{Arc, Mutex};
 use wassily::prelude::palette::{GetHue, IntoColor, Okhsl, Okhsv, Srgb, Xyz};
-use wassily::prelude::NoiseFn;
+use wassily::prelude::{noise2d, NoiseFn, NoiseOpts};
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Rotation {
@@ -33,7 +33,7 @@ impl std::fmt::Display for Rotation {
     }
 }
 
-fn apply_rotation(img: &RgbaImage, rotation: Rotation) -> RgbaImage {
+pub fn apply_rotation(img: &RgbaImage, rotation: Rotation) -> RgbaImage {
     match rotation {
         Rotation::Deg0 => img.clone(),
         Rotation::Deg90 => image::imageops::rotate90(img),
@@ -188,7 +188,7 @@ impl ThumbCache {
         let rotation = controls.rotation.unwrap_or(Rotation::Deg0);
         let key = (path.clone(), controls.blur.to_bits(), rotation);
         if self.key.as_ref() != Some(&key) {
-            let orig = original(&path)?;
+            let orig = original(&path, NOISE_SIDE)?;
             // Downscale first so blurring stays instant while sliding; the
             // blur sigma shrinks with the image to stay visually faithful.
             let (ow, oh) = (orig.width() as f32, orig.height() as f32);
@@ -275,26 +275,54 @@ impl NoiseFn<f64, 2> for SharedImgNoise {
     }
 }
 
-static ORIGINAL: Mutex<Option<(String, Option<Arc<RgbaImage>>)>> = Mutex::new(None);
+/// The image noise as a flow field made from it sees it: tiled by the noise
+/// scale and carried through the field's turbulence and symmetry, so that
+/// whatever samples it lines up with the curves.
+pub struct FieldImage {
+    pub noise: Box<dyn NoiseFn<f64, 2>>,
+    /// The field's scales, with a factor of 1.
+    pub opts: NoiseOpts,
+}
+
+impl FieldImage {
+    /// The color map value at canvas point (x, y), in [-1, 1].
+    pub fn value(&self, x: f32, y: f32) -> f32 {
+        noise2d(&self.noise, &self.opts, x, y)
+    }
+}
 
-/// The decoded source image, cached until the path changes. Failures are
-/// cached too, so a missing or broken file is not re-read every frame, and
-/// decoding is shielded against panics in the image decoder.
-fn original(path: &str) -> Option<Arc<RgbaImage>> {
+/// How many decoded source images stay cached: the flow field, the
+/// background, the paper and a sprite may each use a different image.
+const ORIGINALS: usize = 4;
+/// The longest side an image is sampled as noise at. The flow field samples
+/// at canvas resolution, so anything beyond this only slows the color map
+/// down.
+pub const NOISE_SIDE: u32 = 1600;
+type OriginalKey = (String, u32);
+static ORIGINAL: Mutex<Vec<(OriginalKey, Option<Arc<RgbaImage>>)>> = Mutex::new(Vec::new());
+
+/// The decoded source image, scaled down to at most `max_side` on its
+/// longer side, cached until it falls out of the small most-recently-used
+/// cache. Failures are cached too, so a missing or broken file is not re-read
+/// every frame, and decoding is shielded against panics in the image
+/// decoder.
+pub fn original(path: &str, max_side: u32) -> Option<Arc<RgbaImage>> {
     let mut cache = ORIGINAL.lock().unwrap_or_else(|e| e.into_inner());
-    if let Some((p, img)) = cache.as_ref() {
-        if p == path {
-            return img.clone();
-        }
+    if let Some(i) = cache
+        .iter()
+        .position(|((p, side), _)| p == path && *side == max_side)
+    {
+        let entry = cache.remove(i);
+        let img = entry.1.clone();
+        cache.insert(0, entry);
+        return img;
     }
     let decoded = std::panic::catch_unwind(|| {
         image::open(path).ok().map(|i| {
-            // The flow field samples at canvas resolution, so anything
-            // beyond ~1600px only slows the color map down.
             let img = i.to_rgba8();
             let side = img.width().max(img.height());
-            if side > 1600 {
-                let s = 1600.0 / side as f32;
+            if side > max_side {
+                let s = max_side as f32 / side as f32;
                 image::imageops::resize(
                     &img,
                     ((img.width() as f32 * s) as u32).max(1),
@@ -310,17 +338,44 @@ fn original(path: &str) -> Option<Arc<RgbaImage>> {
     .flatten()
     .map(Arc::new);
     if decoded.is_none() {
-        eprintln!("could not read noise image {path}");
+        eprintln!("could not read image {path}");
     }
-    *cache = Some((path.to_string(), decoded.clone()));
+    cache.insert(0, ((path.to_string(), max_side), decoded.clone()));
+    cache.truncate(ORIGINALS);
     decoded
 }
 
+static TURNED: Mutex<Option<((String, Rotation), Arc<RgbaImage>)>> = Mutex::new(None);
+
+/// The image at `path` at full resolution, turned by `rotation`, for drawing
+/// rather than sampling. The last turned image is kept, so renders do not
+/// turn it again.
+pub fn full_image(path: &str, rotation: Rotation) -> Option<Arc<RgbaImage>> {
+    let img = original(path, u32::MAX)?;
+    if rotation == Rotation::Deg0 {
+        return Some(img);
+    }
+    let key = (path.to_string(), rotation);
+    let mut cache = TURNED.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some((k, turned)) = cache.as_ref() {
+        if *k == key {
+            return Some(turned.clone());
+        }
+    }
+    let turned = Arc::new(apply_rotation(&img, rotation));
+    *cache = Some((key, turned.clone()));
+    Some(turned)
+}
+
 type NoiseKey = (String, ColorMap, u32, Rotation);
-static CACHE: Mutex<Option<(NoiseKey, Arc<ImgNoise>)>> = Mutex::new(None);
+/// How many processed images stay cached: the flow field, a density map
+/// and a magnitude source may each want a different color map of one image.
+const CACHED: usize = 4;
+static CACHE: Mutex<Vec<(NoiseKey, Arc<ImgNoise>)>> = Mutex::new(Vec::new());
 
 /// The image noise for (path, colormap, blur, rotation), processed once and
-/// cached until any of them change. None if the image cannot be read.
+/// cached until it falls out of the small most-recently-used cache. None if
+/// the image cannot be read.
 pub fn cached_noise(
     path: &str,
     colormap: ColorMap,
@@ -329,12 +384,13 @@ pub fn cached_noise(
 ) -> Option<Arc<ImgNoise>> {
     let key: NoiseKey = (path.to_string(), colormap, blur.to_bits(), rotation);
     let mut cache = CACHE.lock().unwrap_or_else(|e| e.into_inner());
-    if let Some((k, n)) = cache.as_ref() {
-        if *k == key {
-            return Some(n.clone());
-        }
+    if let Some(i) = cache.iter().position(|(k, _)| *k == key) {
+        let entry = cache.remove(i);
+        let noise = entry.1.clone();
+        cache.insert(0, entry);
+        return Some(noise);
     }
-    let orig = original(path)?;
+    let orig = original(path, NOISE_SIDE)?;
     if orig.width() == 0 || orig.height() == 0 {
         return None;
     }
@@ -345,7 +401,8 @@ pub fn cached_noise(
         rotated
     };
     let noise = Arc::new(ImgNoise::new(&processed, colormap));
-    *cache = Some((key, noise.clone()));
+    cache.insert(0, (key, noise.clone()));
+    cache.truncate(CACHED);
     Some(noise)
 }
 
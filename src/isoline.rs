@@ -0,0 +1,231 @@
+//! The Isolines style: a topographic map of the flow field's noise instead
+//! of streamlines. The noise is sampled on a grid, traced with marching
+//! squares at evenly spaced levels, and optionally filled with the palette
+//! between levels.
+
+use std::collections::HashMap;
+
+use crate::gui::{color_picker, section, SliderRow};
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+use wassily::prelude::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct IsolineControls {
+    pub levels: u32,
+    /// Grid cell size in pixels; smaller traces finer detail.
+    pub resolution: f32,
+    /// Fill the bands between levels with the palette.
+    pub fill: bool,
+    /// Draw the contour lines.
+    pub lines: bool,
+    /// Round off the grid corners with a spline.
+    pub smooth: bool,
+    /// The line color over filled bands; unfilled maps draw lines in the
+    /// palette.
+    pub line_color: egui::Color32,
+}
+
+impl Default for IsolineControls {
+    fn default() -> Self {
+        Self {
+            levels: 12,
+            resolution: 4.0,
+            fill: false,
+            lines: true,
+            smooth: true,
+            line_color: egui::Color32::BLACK,
+        }
+    }
+}
+
+impl IsolineControls {
+    pub fn ui(&mut self, ui: &mut egui::Ui) {
+        section(ui, "Isolines");
+        egui::Grid::new("isolines")
+            .spacing((15.0, 10.0))
+            .min_col_width(90.0)
+            .show(ui, |ui| {
+                SliderRow::new("Levels", &mut self.levels, 12, 1..=64)
+                    .hover(&["Contours, evenly spaced over", "the range of the noise."])
+                    .show(ui);
+                SliderRow::new("Resolution", &mut self.resolution, 4.0, 1.0..=20.0)
+                    .hover(&["Sampling grid cell in pixels;", "smaller traces finer detail."])
+                    .steps(0.5, 2.0)
+                    .decimals(1)
+                    .show(ui);
+                ui.label("Fill").on_hover_ui(|ui| {
+                    ui.colored_label(egui::Color32::ORANGE, "Fill the bands between");
+                    ui.colored_label(egui::Color32::ORANGE, "levels with the palette.");
+                });
+                ui.checkbox(&mut self.fill, "");
+                ui.end_row();
+                ui.label("Lines");
+                ui.checkbox(&mut self.lines, "");
+                ui.end_row();
+                ui.label("Smooth");
+                ui.checkbox(&mut self.smooth, "");
+                ui.end_row();
+                if self.fill && self.lines {
+                    color_picker(ui, "Line Color", &mut self.line_color);
+                }
+            });
+    }
+}
+
+/// Samples of a scalar field on a regular grid covering the canvas.
+pub struct ScalarGrid {
+    pub nx: usize,
+    pub ny: usize,
+    pub cell: f32,
+    pub values: Vec<f32>,
+}
+
+impl ScalarGrid {
+    /// Sample `f` every `cell` canvas units over a `w` by `h` canvas.
+    pub fn sample(w: f32, h: f32, cell: f32, f: impl Fn(f32, f32) -> f32) -> Self {
+        let cell = cell.max(0.5);
+        let nx = (w / cell).ceil() as usize + 1;
+        let ny = (h / cell).ceil() as usize + 1;
+        let mut values = Vec::with_capacity(nx * ny);
+        for j in 0..ny {
+            for i in 0..nx {
+                values.push(f(i as f32 * cell, j as f32 * cell));
+            }
+        }
+        Self {
+            nx,
+            ny,
+            cell,
+            values,
+        }
+    }
+
+    fn at(&self, i: usize, j: usize) -> f32 {
+        self.values[j * self.nx + i]
+    }
+
+    /// The smallest and largest samples.
+    pub fn range(&self) -> (f32, f32) {
+        self.values
+            .iter()
+            .fold((f32::MAX, f32::MIN), |(lo, hi), v| (lo.min(*v), hi.max(*v)))
+    }
+
+    /// Soften the samples with a blur of about `radius` canvas units.
+    pub fn blur(&mut self, radius: f32) {
+        let r = (radius / self.cell).round() as usize;
+        let packed: Vec<[f32; 4]> = self.values.iter().map(|v| [*v; 4]).collect();
+        self.values = crate::effects::blur(packed, self.nx, self.ny, r)
+            .iter()
+            .map(|c| c[0])
+            .collect();
+    }
+
+    /// The bilinearly interpolated value at canvas point (x, y).
+    pub fn value(&self, x: f32, y: f32) -> f32 {
+        let gx = (x / self.cell).clamp(0.0, (self.nx - 1) as f32);
+        let gy = (y / self.cell).clamp(0.0, (self.ny - 1) as f32);
+        let (i, j) = (
+            (gx as usize).min(self.nx - 2),
+            (gy as usize).min(self.ny - 2),
+        );
+        let (tx, ty) = (gx - i as f32, gy - j as f32);
+        let top = self.at(i, j) * (1.0 - tx) + self.at(i + 1, j) * tx;
+        let bottom = self.at(i, j + 1) * (1.0 - tx) + self.at(i + 1, j + 1) * tx;
+        top * (1.0 - ty) + bottom * ty
+    }
+
+    /// The contour lines at level `t` by marching squares, chained into
+    /// polylines; closed loops repeat their first point at the end.
+    pub fn contours(&self, t: f32) -> Vec<Vec<Point>> {
+        // Crossings live on grid edges: kind 0 runs right from (i, j),
+        // kind 1 runs down from it.
+        let key = |i: usize, j: usize, kind: u64| (((j * self.nx + i) as u64) << 1) | kind;
+        let mut pos: HashMap<u64, Point> = HashMap::new();
+        let mut links: HashMap<u64, Vec<u64>> = HashMap::new();
+        type Corner = (f32, f32, f32);
+        let mut crossing = |k: u64, (x0, y0, v0): Corner, (x1, y1, v1): Corner| {
+            pos.entry(k).or_insert_with(|| {
+                let s = ((t - v0) / (v1 - v0)).clamp(0.0, 1.0);
+                pt(x0 + (x1 - x0) * s, y0 + (y1 - y0) * s)
+            });
+            k
+        };
+        for j in 0..self.ny - 1 {
+            for i in 0..self.nx - 1 {
+                let c = self.cell;
+                let (x, y) = (i as f32 * c, j as f32 * c);
+                let a = (x, y, self.at(i, j));
+                let b = (x + c, y, self.at(i + 1, j));
+                let cc = (x + c, y + c, self.at(i + 1, j + 1));
+                let d = (x, y + c, self.at(i, j + 1));
+                let above = |p: Corner| p.2 >= t;
+                let mut cut = |k: u64, p: Corner, q: Corner| {
+                    (above(p) != above(q)).then(|| crossing(k, p, q))
+                };
+                let top = cut(key(i, j, 0), a, b);
+                let right = cut(key(i + 1, j, 1), b, cc);
+                let bottom = cut(key(i, j + 1, 0), d, cc);
+                let left = cut(key(i, j, 1), a, d);
+                let mut segs: Vec<(u64, u64)> = Vec::with_capacity(2);
+                match (top, right, bottom, left) {
+                    (Some(tp), Some(r), Some(bt), Some(l)) => {
+                        // A saddle: the cell center decides which corners
+                        // the contours cut off.
+                        let center = 0.25 * (a.2 + b.2 + cc.2 + d.2);
+                        if (center >= t) == above(a) {
+                            segs.push((tp, r));
+                            segs.push((bt, l));
+                        } else {
+                            segs.push((l, tp));
+                            segs.push((r, bt));
+                        }
+                    }
+                    _ => {
+                        let ends: Vec<u64> =
+                            [top, right, bottom, left].into_iter().flatten().collect();
+                        if ends.len() == 2 {
+                            segs.push((ends[0], ends[1]));
+                        }
+                    }
+                }
+                for (p, q) in segs {
+                    links.entry(p).or_default().push(q);
+                    links.entry(q).or_default().push(p);
+                }
+            }
+        }
+        // Walk the chains, open ones (from an end with a single link) first.
+        let mut starts: Vec<u64> = links
+            .iter()
+            .filter(|(_, v)| v.len() == 1)
+            .map(|(k, _)| *k)
+            .collect();
+        starts.sort_unstable();
+        let mut rest: Vec<u64> = links.keys().copied().collect();
+        rest.sort_unstable();
+        starts.extend(rest);
+        let mut lines = Vec::new();
+        for start in starts {
+            if links.get(&start).is_none_or(|v| v.is_empty()) {
+                continue;
+            }
+            let mut line = vec![pos[&start]];
+            let mut cur = start;
+            while let Some(next) = links.get_mut(&cur).and_then(|v| v.pop()) {
+                if let Some(back) = links.get_mut(&next) {
+                    if let Some(ix) = back.iter().position(|k| *k == cur) {
+                        back.swap_remove(ix);
+                    }
+                }
+                line.push(pos[&next]);
+                cur = next;
+            }
+            if line.len() > 1 {
+                lines.push(line);
+            }
+        }
+        lines
+    }
+}
@@ -0,0 +1,201 @@
+//! The layer stack. Every layer is a full set of `Controls` with its own
+//! style, seeds, field, palette and opacity. The first layer is the document
+//! itself, which also owns the canvas size and background; the others are
+//! drawn over it in order.
+
+use crate::common::Controls;
+use crate::gui::{pick_list, section, SliderRow, SPACE};
+use crate::presets::ribbons;
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+use wassily::prelude::*;
+
+/// How a layer's curves combine with everything beneath them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Blend {
+    Normal,
+    /// Darkens like ink on paper.
+    Multiply,
+    /// Lightens, so faint curves glow on dark grounds.
+    Screen,
+    Overlay,
+    SoftLight,
+    Difference,
+    /// Adds the colors, building to white.
+    Additive,
+}
+
+impl std::fmt::Display for Blend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Blend::Normal => "Normal",
+                Blend::Multiply => "Multiply",
+                Blend::Screen => "Screen",
+                Blend::Overlay => "Overlay",
+                Blend::SoftLight => "Soft Light",
+                Blend::Difference => "Difference",
+                Blend::Additive => "Additive",
+            }
+        )
+    }
+}
+
+impl Blend {
+    pub fn mode(self) -> BlendMode {
+        match self {
+            Blend::Normal => BlendMode::SourceOver,
+            Blend::Multiply => BlendMode::Multiply,
+            Blend::Screen => BlendMode::Screen,
+            Blend::Overlay => BlendMode::Overlay,
+            Blend::SoftLight => BlendMode::SoftLight,
+            Blend::Difference => BlendMode::Difference,
+            Blend::Additive => BlendMode::Plus,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LayerControls {
+    pub name: String,
+    /// Hidden layers keep their settings but are not drawn.
+    pub visible: bool,
+    /// Opacity of the whole layer when composited, on top of the opacity
+    /// of its curves.
+    pub opacity: f32,
+    /// How the layer's curves composite with each other, and the layer as
+    /// a whole with the background and the layers below.
+    #[serde(default = "default_blend")]
+    pub blend: Option<Blend>,
+    /// Seeds the layer's rng. Given when the layer is made, so its curves
+    /// stay put when the stack is reordered; 0 in files saved before layers
+    /// had seeds, which are seeded by their place in the stack instead. The
+    /// base layer carries on with the background's rng.
+    #[serde(default)]
+    pub seed: u64,
+}
+
+fn default_blend() -> Option<Blend> {
+    Some(Blend::Normal)
+}
+
+impl Default for LayerControls {
+    fn default() -> Self {
+        Self {
+            name: "Base".to_string(),
+            visible: true,
+            opacity: 1.0,
+            blend: Some(Blend::Normal),
+            seed: 0,
+        }
+    }
+}
+
+impl LayerControls {
+    pub fn ui(&mut self, ui: &mut egui::Ui) {
+        use Blend::*;
+        egui::Grid::new("layer")
+            .spacing((15.0, 10.0))
+            .min_col_width(90.0)
+            .show(ui, |ui| {
+                ui.label("Name");
+                ui.add(egui::TextEdit::singleline(&mut self.name).desired_width(150.0));
+                ui.end_row();
+                SliderRow::new("Layer Opacity", &mut self.opacity, 1.0, 0.0..=1.0)
+                    .hover(&["Opacity of the whole layer", "over the layers below."])
+                    .steps(0.02, 0.1)
+                    .decimals(2)
+                    .show(ui);
+                pick_list(
+                    ui,
+                    "Blend",
+                    &[
+                        Normal, Multiply, Screen, Overlay, SoftLight, Difference, Additive,
+                    ],
+                    &mut self.blend,
+                );
+            });
+    }
+}
+
+/// A new non-zero layer seed.
+fn fresh_seed() -> u64 {
+    rand::random::<u64>().max(1)
+}
+
+/// A fresh layer to add to the stack, using the canvas of `base`.
+fn new_layer(base: &Controls) -> Controls {
+    let mut layer = ribbons();
+    layer.width = base.width;
+    layer.height = base.height;
+    layer.layer.name = format!("Layer {}", base.layers.len() + 1);
+    layer.layer.seed = fresh_seed();
+    layer
+}
+
+/// The layer list, top layer first, with buttons to add, duplicate, move
+/// and remove layers. `selected` indexes the stack, 0 being the base layer,
+/// which always stays at the bottom.
+pub fn stack_ui(ui: &mut egui::Ui, controls: &mut Controls, selected: &mut usize) {
+    section(ui, "Layers");
+    *selected = (*selected).min(controls.layers.len());
+    for k in (0..=controls.layers.len()).rev() {
+        let layer = controls.layer_mut(k);
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut layer.layer.visible, "")
+                .on_hover_text("Show or hide the layer.");
+            if ui
+                .selectable_label(*selected == k, layer.layer.name.as_str())
+                .clicked()
+            {
+                *selected = k;
+            }
+        });
+    }
+    ui.add_space(SPACE);
+    ui.horizontal(|ui| {
+        if ui.button("Add").clicked() {
+            let layer = new_layer(controls);
+            controls.layers.push(layer);
+            *selected = controls.layers.len();
+        }
+        if ui.button("Duplicate").clicked() {
+            let mut copy = controls.layer_mut(*selected).clone();
+            copy.layers.clear();
+            copy.layer.name = format!("{} copy", copy.layer.name);
+            // The base layer and layers from older files have no seed of
+            // their own, so the copy gets one that stays with it.
+            if copy.layer.seed == 0 {
+                copy.layer.seed = fresh_seed();
+            }
+            controls.layers.insert(*selected, copy);
+            *selected += 1;
+        }
+        let n = controls.layers.len();
+        if ui
+            .add_enabled(*selected > 0 && *selected < n, egui::Button::new("Up"))
+            .clicked()
+        {
+            controls.layers.swap(*selected - 1, *selected);
+            *selected += 1;
+        }
+        if ui
+            .add_enabled(*selected > 1, egui::Button::new("Down"))
+            .clicked()
+        {
+            controls.layers.swap(*selected - 2, *selected - 1);
+            *selected -= 1;
+        }
+        if ui
+            .add_enabled(*selected > 0, egui::Button::new("Remove"))
+            .clicked()
+        {
+            controls.layers.remove(*selected - 1);
+            *selected -= 1;
+        }
+    });
+    ui.add_space(SPACE);
+    controls.layer_mut(*selected).layer.ui(ui);
+}
@@ -0,0 +1,126 @@
+//! Magnitude-aware flow: a second scalar over the canvas that modulates how
+//! fast curves walk, how quickly they turn, and how wide they are drawn, so
+//! curves bunch up and thin out with the field instead of marching evenly.
+
+use crate::gui::{pick_list, section, SliderRow};
+use crate::imgnoise::FieldImage;
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum MagnitudeSource {
+    /// The flow field's own noise value.
+    Noise,
+    /// The steepness of the flow field's noise.
+    Gradient,
+    /// The noise image through its color map, sampled as an Image field
+    /// would.
+    Image,
+}
+
+impl std::fmt::Display for MagnitudeSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                MagnitudeSource::Noise => "Noise Value",
+                MagnitudeSource::Gradient => "Gradient",
+                MagnitudeSource::Image => "Image",
+            }
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MagnitudeControls {
+    pub source: Option<MagnitudeSource>,
+    /// How strongly the magnitude scales the step length; 0 is off.
+    pub step: f32,
+    /// How strongly the magnitude scales the turning speed; 0 is off.
+    pub speed: f32,
+    /// How strongly the magnitude scales the stroke width; 0 is off.
+    pub width: f32,
+    /// Use one minus the magnitude, so weak regions get the long steps.
+    pub invert: bool,
+}
+
+impl Default for MagnitudeControls {
+    fn default() -> Self {
+        Self {
+            source: Some(MagnitudeSource::Noise),
+            step: 0.0,
+            speed: 0.0,
+            width: 0.0,
+            invert: false,
+        }
+    }
+}
+
+impl MagnitudeControls {
+    pub fn ui(&mut self, ui: &mut egui::Ui) {
+        use MagnitudeSource::*;
+        section(ui, "Magnitude");
+        egui::Grid::new("magnitude")
+            .spacing((15.0, 10.0))
+            .min_col_width(90.0)
+            .show(ui, |ui| {
+                pick_list(ui, "Source", &[Noise, Gradient, Image], &mut self.source);
+                SliderRow::new("Step", &mut self.step, 0.0, 0.0..=1.0)
+                    .hover(&["Step length follows the", "magnitude; 0 is off."])
+                    .steps(0.05, 0.1)
+                    .decimals(2)
+                    .show(ui);
+                SliderRow::new("Turning", &mut self.speed, 0.0, 0.0..=1.0)
+                    .hover(&["Turning speed follows the", "magnitude; 0 is off."])
+                    .steps(0.05, 0.1)
+                    .decimals(2)
+                    .show(ui);
+                SliderRow::new("Width", &mut self.width, 0.0, 0.0..=1.0)
+                    .hover(&["Stroke width follows the", "magnitude; 0 is off."])
+                    .steps(0.05, 0.1)
+                    .decimals(2)
+                    .show(ui);
+                ui.label("Invert").on_hover_ui(|ui| {
+                    ui.colored_label(egui::Color32::ORANGE, "Use the weak regions of the");
+                    ui.colored_label(egui::Color32::ORANGE, "magnitude as the strong ones.");
+                });
+                ui.checkbox(&mut self.invert, "");
+                ui.end_row();
+            });
+    }
+}
+
+/// The render-time modulation settings a `Field` samples.
+pub struct Modulation {
+    pub source: MagnitudeSource,
+    pub step: f32,
+    pub speed: f32,
+    pub width: f32,
+    pub invert: bool,
+    /// Image source: the processed noise image, if there is one.
+    pub image: Option<FieldImage>,
+    /// Gradient source: converts canvas-unit slopes to roughly unit slopes
+    /// per noise period.
+    pub grad_norm: f32,
+}
+
+impl Modulation {
+    pub fn off() -> Self {
+        Self {
+            source: MagnitudeSource::Noise,
+            step: 0.0,
+            speed: 0.0,
+            width: 0.0,
+            invert: false,
+            image: None,
+            grad_norm: 1.0,
+        }
+    }
+}
+
+/// Scale factor for a magnitude `m` in [0, 1] at strength `amount`: 1 on
+/// average, spanning 1 - amount to 1 + amount.
+pub fn factor(m: f32, amount: f32) -> f32 {
+    (1.0 + amount * (2.0 * m - 1.0)).max(0.05)
+}
@@ -5,27 +5,41 @@ use std::sync::atomic::Ordering;
 
 mod art;
 mod background;
+mod brush;
 mod color;
 mod common;
+mod density;
 mod dot;
+mod effects;
 mod extrude;
 mod field;
 mod fractal;
 mod gradient;
 mod gui;
+mod harmony;
+mod hatch;
 mod imgnoise;
+mod isoline;
+mod layer;
 mod location;
+mod magnitude;
 mod noise;
 mod presets;
+mod relief;
+mod ring;
 mod sine;
 mod size;
+mod spline;
+mod stops;
+mod stroke;
+mod symmetry;
 
 use crate::art::draw;
 use crate::background::Background;
 use crate::common::*;
 use crate::gui::{action_button, color_picker, numeric, pick_list, section, SliderRow, SPACE};
 use crate::location::Location;
-use crate::noise::NoiseFunction;
+use crate::noise::{FieldMode, NoiseFunction};
 use crate::presets::*;
 
 pub fn main() -> eframe::Result {
@@ -55,21 +69,27 @@ pub fn main() -> eframe::Result {
                     app.last_drawn = controls.clone();
                     app.controls = controls;
                 }
+                if let Some(palettes) = eframe::get_value(storage, USER_PALETTES_KEY) {
+                    app.user_palettes = palettes;
+                }
             }
             Ok(Box::new(app))
         }),
     )
 }
 
-/// Render the artwork at full resolution and save it to `path`, along with
-/// a json file of the parameters that produced it.
+/// Render the artwork at full resolution and save it to `path` as a PNG, or
+/// a TIFF for a .tif or .tiff path, along with a json file of the parameters
+/// that produced it.
 pub fn print(controls: Controls, mut path: PathBuf) {
     if path.extension().is_none() {
         path.set_extension("png");
     }
     let scale = std::cmp::max(controls.width, controls.height).max(1) as f32 / 1000.0;
     let canvas = draw(&controls, scale);
-    canvas.save_png(&path);
+    if let Err(e) = save_image(&canvas.pixmap, &path) {
+        eprintln!("failed to write {}: {e}", path.display());
+    }
     let params = path.with_extension("json");
     match serde_json::to_string_pretty(&controls) {
         Ok(json) => {
@@ -81,6 +101,23 @@ pub fn print(controls: Controls, mut path: PathBuf) {
     }
 }
 
+/// Write `pixmap` with straight (unpremultiplied) alpha, so transparent
+/// renders composite correctly in other tools. The format follows the
+/// extension of `path`.
+fn save_image(pixmap: &wassily::prelude::Pixmap, path: &std::path::Path) -> image::ImageResult<()> {
+    let data = pixmap
+        .pixels()
+        .iter()
+        .flat_map(|p| {
+            let c = p.demultiply();
+            [c.red(), c.green(), c.blue(), c.alpha()]
+        })
+        .collect();
+    image::RgbaImage::from_raw(pixmap.width(), pixmap.height(), data)
+        .expect("pixmap holds width * height pixels")
+        .save(path)
+}
+
 /// The first `k2_N.png` name not already present in `dir`.
 fn next_sketch_name(dir: &std::path::Path) -> String {
     let mut num = 0;
@@ -157,43 +194,59 @@ impl K2 {
                     self.controls.height *= 300;
                 }
 
-                let mut preset = self.controls.preset;
-                if pick_list(
+                pick_list(
                     ui,
-                    "Preset",
+                    "Background",
                     &[
-                        Ribbons, Worms, Solar, Vortex, Canyon, Splat, Tubes, Ducts, RedDwarf,
+                        LightGrain, LightFiber, DarkGrain, DarkFiber, ColorGrain, White, Black,
+                        Solid, Linear, Radial, Conic, Wash, Paper, Field, Image, Transparent,
                     ],
-                    &mut preset,
-                ) {
-                    if let Some(p) = preset {
-                        // Keep the chosen noise image across preset loads.
-                        let image_noise = self.controls.image_noise.clone();
-                        self.controls = load_preset(p);
-                        self.controls.image_noise = image_noise;
-                        self.pending_draw = true;
-                    }
-                }
+                    &mut self.controls.background,
+                );
             });
 
+        crate::layer::stack_ui(ui, &mut self.controls, &mut self.layer);
+
         ui.add_space(SPACE);
         ui.separator();
         ui.add_space(SPACE);
 
+        // Everything below edits the selected layer.
+        let c = self.controls.layer_mut(self.layer);
         egui::Grid::new("style")
             .spacing((15.0, 10.0))
             .min_col_width(90.0)
             .show(ui, |ui| {
+                let mut preset = c.preset;
+                if pick_list(
+                    ui,
+                    "Preset",
+                    &[
+                        Ribbons, Worms, Solar, Vortex, Canyon, Splat, Tubes, Ducts, RedDwarf,
+                    ],
+                    &mut preset,
+                ) {
+                    if let Some(p) = preset {
+                        // Keep the chosen noise image across preset loads, and
+                        // the layer's place in the stack.
+                        let mut loaded = load_preset(p);
+                        loaded.image_noise = c.image_noise.clone();
+                        loaded.layer = c.layer.clone();
+                        loaded.layers = std::mem::take(&mut c.layers);
+                        *c = loaded;
+                        self.pending_draw = true;
+                    }
+                }
                 pick_list(
                     ui,
                     "Curve Style",
-                    &[Line, Dots, Extrusion, Strips],
-                    &mut self.controls.curve_style,
+                    &[Line, Dots, Extrusion, Strips, Brush, Hatch, Rings, Isolines],
+                    &mut c.curve_style,
                 );
                 ui.label("Direction");
                 ui.horizontal(|ui| {
-                    ui.radio_value(&mut self.controls.curve_direction, Some(OneSided), "One");
-                    ui.radio_value(&mut self.controls.curve_direction, Some(TwoSided), "Two");
+                    ui.radio_value(&mut c.curve_direction, Some(OneSided), "One");
+                    ui.radio_value(&mut c.curve_direction, Some(TwoSided), "Two");
                 });
                 ui.end_row();
                 pick_list(
@@ -203,47 +256,44 @@ impl K2 {
                         Fbm, BasicMulti, HybridMulti, Billow, Ridged, Value, Cylinders,
                         Worley, Curl, Sinusoidal, Image,
                     ],
-                    &mut self.controls.noise_controls.noise_function,
+                    &mut c.noise_controls.noise_function,
                 );
-                if self.controls.curve_style == Some(CurveStyle::Strips) {
-                    // Strips pair neighboring curves, which only makes sense
-                    // from an ordered column of seeds.
-                    self.controls.location = Some(Location::Line);
-                    ui.label("Locations");
-                    ui.add_enabled(
-                        false,
-                        egui::Button::new("Line").min_size(egui::vec2(150.0, 0.0)),
-                    )
-                    .on_disabled_hover_ui(|ui| {
-                        ui.colored_label(
-                            egui::Color32::ORANGE,
-                            "Locked to Line while the",
-                        );
-                        ui.colored_label(egui::Color32::ORANGE, "Strips style is selected.");
-                    });
-                    ui.end_row();
-                } else {
-                    pick_list(
-                        ui,
-                        "Locations",
-                        &[
-                            Location::Grid,
-                            Location::Rand,
-                            Location::Halton,
-                            Location::Poisson,
-                            Location::Circle,
-                            Location::Lissajous,
-                            Location::Box,
-                            Location::Line,
-                            Location::Even,
-                        ],
-                        &mut self.controls.location,
-                    );
-                }
-                if self.controls.location == Some(Location::Line) {
+                pick_list(
+                    ui,
+                    "Field Mode",
+                    &[
+                        FieldMode::Value,
+                        FieldMode::Gradient,
+                        FieldMode::Contour,
+                    ],
+                    &mut c.noise_controls.field_mode,
+                );
+                ui.label("Reverse").on_hover_ui(|ui| {
+                    ui.colored_label(egui::Color32::ORANGE, "Run the curves against");
+                    ui.colored_label(egui::Color32::ORANGE, "the flow.");
+                });
+                ui.checkbox(&mut c.noise_controls.reverse, "");
+                ui.end_row();
+                pick_list(
+                    ui,
+                    "Locations",
+                    &[
+                        Location::Grid,
+                        Location::Rand,
+                        Location::Halton,
+                        Location::Poisson,
+                        Location::Circle,
+                        Location::Lissajous,
+                        Location::Box,
+                        Location::Line,
+                        Location::Even,
+                    ],
+                    &mut c.location,
+                );
+                if c.location == Some(Location::Line) {
                     SliderRow::new(
                         "Angle",
-                        &mut self.controls.column_angle,
+                        &mut c.column_angle,
                         0.0,
                         0.0..=180.0,
                     )
@@ -255,7 +305,7 @@ impl K2 {
                     .show(ui);
                     SliderRow::new(
                         "Shift",
-                        &mut self.controls.line_shift,
+                        &mut c.line_shift,
                         0.0,
                         -50.0..=50.0,
                     )
@@ -267,15 +317,6 @@ impl K2 {
                     .steps(1.0, 5.0)
                     .show(ui);
                 }
-                pick_list(
-                    ui,
-                    "Background",
-                    &[
-                        LightGrain, LightFiber, DarkGrain, DarkFiber, ColorGrain, White, Black,
-                        Solid,
-                    ],
-                    &mut self.controls.background,
-                );
             });
 
         ui.add_space(SPACE);
@@ -289,7 +330,7 @@ impl K2 {
                 numeric(
                     ui,
                     "Density",
-                    &mut self.controls.density,
+                    &mut c.density,
                     d.density,
                     5.0..=100.0,
                     5.0,
@@ -298,7 +339,7 @@ impl K2 {
                 numeric(
                     ui,
                     "Point Spacing",
-                    &mut self.controls.spacing,
+                    &mut c.spacing,
                     d.spacing,
                     1.0..=100.0,
                     1.0,
@@ -307,7 +348,7 @@ impl K2 {
                 numeric(
                     ui,
                     "Curve Length",
-                    &mut self.controls.curve_length,
+                    &mut c.curve_length,
                     d.curve_length,
                     0..=1000,
                     1.0,
@@ -331,12 +372,12 @@ impl K2 {
                         "endpoints are visible.",
                     );
                 });
-                ui.checkbox(&mut self.controls.hide_ends, "");
+                ui.checkbox(&mut c.hide_ends, "");
                 ui.end_row();
                 numeric(
                     ui,
                     "Noise Scale",
-                    &mut self.controls.noise_controls.noise_scale,
+                    &mut c.noise_controls.noise_scale,
                     d.noise_controls.noise_scale,
                     0.1..=20.0,
                     0.1,
@@ -345,7 +386,7 @@ impl K2 {
                 numeric(
                     ui,
                     "Noise Factor",
-                    &mut self.controls.noise_controls.noise_factor,
+                    &mut c.noise_controls.noise_factor,
                     d.noise_controls.noise_factor,
                     0.1..=10.0,
                     0.1,
@@ -353,7 +394,7 @@ impl K2 {
                 );
                 SliderRow::new(
                     "Turning Speed",
-                    &mut self.controls.speed,
+                    &mut c.speed,
                     d.speed,
                     0.01..=1.0,
                 )
@@ -365,9 +406,50 @@ impl K2 {
                 .logarithmic()
                 .decimals(2)
                 .show(ui);
+                pick_list(
+                    ui,
+                    "Integrator",
+                    &[
+                        Integrator::Euler,
+                        Integrator::Midpoint,
+                        Integrator::Rk4,
+                        Integrator::Adaptive,
+                    ],
+                    &mut c.integrator,
+                );
+                if c.integrator == Some(Integrator::Adaptive) {
+                    SliderRow::new(
+                        "Tolerance",
+                        &mut c.tolerance,
+                        d.tolerance,
+                        0.001..=1.0,
+                    )
+                    .hover(&[
+                        "Largest position error per",
+                        "step; smaller follows sharp",
+                        "turns more closely.",
+                    ])
+                    .logarithmic()
+                    .decimals(3)
+                    .show(ui);
+                }
+                if c.integrator != Some(Integrator::Euler) {
+                    ui.label("Momentum").on_hover_ui(|ui| {
+                        ui.colored_label(
+                            egui::Color32::ORANGE,
+                            "Keep the turning speed lag;",
+                        );
+                        ui.colored_label(
+                            egui::Color32::ORANGE,
+                            "off follows the field exactly.",
+                        );
+                    });
+                    ui.checkbox(&mut c.momentum, "");
+                    ui.end_row();
+                }
             });
 
-        self.controls.color_mode_controls.ui(ui);
+        c.color_mode_controls.ui(ui, &mut self.user_palettes);
 
         ui.add_space(2.0 * SPACE);
         egui::Grid::new("stroke")
@@ -377,13 +459,13 @@ impl K2 {
                 numeric(
                     ui,
                     "Stroke Width",
-                    &mut self.controls.stroke_width,
+                    &mut c.stroke_width,
                     d.stroke_width,
                     0.0..=25.0,
                     0.5,
                     1,
                 );
-                SliderRow::new("Opacity", &mut self.controls.opacity, 1.0, 0.02..=1.0)
+                SliderRow::new("Opacity", &mut c.opacity, 1.0, 0.02..=1.0)
                     .hover(&[
                         "Curve opacity; low values let",
                         "overlapping curves build up color.",
@@ -411,8 +493,10 @@ impl K2 {
     }
 
     /// Save the current artwork and its parameters on a background thread.
-    fn save_png(&mut self) {
-        let mut dialog = rfd::FileDialog::new().add_filter("PNG image", &["png"]);
+    fn save_image(&mut self) {
+        let mut dialog = rfd::FileDialog::new()
+            .add_filter("PNG image", &["png"])
+            .add_filter("TIFF image", &["tif", "tiff"]);
         if let Some(download_dir) = UserDirs::new().and_then(|d| d.download_dir().map(PathBuf::from))
         {
             dialog = dialog
@@ -450,6 +534,7 @@ impl K2 {
                             Ok(controls) => {
                                 self.last_drawn = controls.clone();
                                 self.controls = controls;
+                                self.layer = 0;
                                 self.pending_draw = true;
                             }
                             Err(e) => eprintln!("could not load {}: {e}", path.display()),
@@ -458,15 +543,16 @@ impl K2 {
                 }
                 let exporting = self.exporting.load(Ordering::Relaxed);
                 if ui
-                    .add_enabled(!exporting, egui::Button::new("Save PNG"))
+                    .add_enabled(!exporting, egui::Button::new("Save Image"))
                     .clicked()
                 {
-                    self.save_png();
+                    self.save_image();
                 }
                 if ui.button("Reset").clicked() {
                     let image_noise = self.controls.image_noise.clone();
                     self.controls = ribbons();
                     self.controls.image_noise = image_noise;
+                    self.layer = 0;
                     self.pending_draw = true;
                 }
                 ui.separator();
@@ -478,13 +564,14 @@ impl K2 {
     }
 
     fn right_panel(&mut self, ui: &mut egui::Ui) {
-        if self.controls.curve_style == Some(CurveStyle::Strips) {
+        let c = self.controls.layer_mut(self.layer);
+        if c.curve_style == Some(CurveStyle::Strips) {
             section(ui, "Strips");
             egui::Grid::new("strips")
                 .spacing((15.0, 10.0))
                 .min_col_width(90.0)
                 .show(ui, |ui| {
-                    SliderRow::new("Gap", &mut self.controls.strip_gap, 0.08, 0.0..=0.6)
+                    SliderRow::new("Gap", &mut c.strip_gap, 0.08, 0.0..=0.6)
                         .hover(&[
                             "Fraction of the channel between",
                             "neighboring curves left open.",
@@ -494,13 +581,24 @@ impl K2 {
                         .show(ui);
                 });
         }
-        if self.controls.curve_style == Some(CurveStyle::Extrusion) {
-            self.controls.extrude_controls.ui(ui);
-        } else if self.controls.curve_style == Some(CurveStyle::Dots) {
-            self.controls.dot_controls.ui(ui);
+        if c.curve_style == Some(CurveStyle::Extrusion) {
+            c.extrude_controls.ui(ui);
+        } else if c.curve_style == Some(CurveStyle::Dots) {
+            c.dot_controls.ui(ui);
+        } else if c.curve_style == Some(CurveStyle::Line) {
+            c.stroke_controls.ui(ui);
+        } else if c.curve_style == Some(CurveStyle::Brush) {
+            c.brush_controls.ui(ui);
+        } else if c.curve_style == Some(CurveStyle::Hatch) {
+            c.hatch_controls.ui(ui);
+        } else if c.curve_style == Some(CurveStyle::Rings) {
+            c.ring_controls.ui(ui);
+        } else if c.curve_style == Some(CurveStyle::Isolines) {
+            c.isoline_controls.ui(ui);
         }
+        c.relief.ui(ui);
         if matches!(
-            self.controls.noise_controls.noise_function,
+            c.noise_controls.noise_function,
             Some(NoiseFunction::Fbm)
                 | Some(NoiseFunction::BasicMulti)
                 | Some(NoiseFunction::HybridMulti)
@@ -508,18 +606,25 @@ impl K2 {
                 | Some(NoiseFunction::Ridged)
                 | Some(NoiseFunction::Curl)
         ) {
-            self.controls.fractal_controls.ui(ui);
+            c.fractal_controls.ui(ui);
+        }
+        if c.noise_controls.noise_function == Some(NoiseFunction::Sinusoidal) {
+            c.sin_controls.ui(ui);
         }
-        if self.controls.noise_controls.noise_function == Some(NoiseFunction::Sinusoidal) {
-            self.controls.sin_controls.ui(ui);
+        if c.noise_controls.noise_function == Some(NoiseFunction::Image) {
+            c.image_noise.ui(ui, &mut self.image_thumb);
         }
-        if self.controls.noise_controls.noise_function == Some(NoiseFunction::Image) {
-            self.controls.image_noise.ui(ui, &mut self.image_thumb);
+        if c.noise_controls.noise_function == Some(NoiseFunction::Worley) {
+            c.worley.ui(ui);
         }
-        if self.controls.noise_controls.noise_function == Some(NoiseFunction::Worley) {
-            self.controls.worley.ui(ui);
+        c.turbulence.ui(ui);
+        c.symmetry.ui(ui);
+        c.magnitude.ui(ui);
+        if c.location == Some(Location::Even) {
+            c.density_map.ui(ui, "Density Map");
+        } else {
+            c.stops.ui(ui);
         }
-        self.controls.turbulence.ui(ui);
         if matches!(
             self.controls.background,
             Some(Background::LightGrain) | Some(Background::DarkGrain) | Some(Background::ColorGrain)
@@ -561,12 +666,26 @@ impl K2 {
                     color_picker(ui, "Color", &mut self.controls.solid_color);
                 });
         }
+        if let Some(
+            bg @ (Background::Linear
+            | Background::Radial
+            | Background::Conic
+            | Background::Wash
+            | Background::Paper
+            | Background::Field
+            | Background::Image),
+        ) = self.controls.background
+        {
+            self.controls.background_controls.ui(ui, bg);
+        }
+        self.controls.effects.ui(ui);
     }
 }
 
 impl eframe::App for K2 {
     fn save(&mut self, storage: &mut dyn eframe::Storage) {
         eframe::set_value(storage, eframe::APP_KEY, &self.controls);
+        eframe::set_value(storage, USER_PALETTES_KEY, &self.user_palettes);
     }
 
     fn ui(&mut self, ui: &mut egui::Ui, _frame: &mut eframe::Frame) {
@@ -611,7 +730,12 @@ impl eframe::App for K2 {
                     let s = (avail.x / self.image_logical.x)
                         .min(avail.y / self.image_logical.y)
                         .min(1.0);
-                    ui.add(egui::Image::new(texture).fit_to_exact_size(self.image_logical * s));
+                    let (rect, _) =
+                        ui.allocate_exact_size(self.image_logical * s, egui::Sense::hover());
+                    if self.last_drawn.background == Some(Background::Transparent) {
+                        crate::gui::checkerboard(ui.painter(), rect);
+                    }
+                    egui::Image::new(texture).paint_at(ui, rect);
                 });
             }
         });
@@ -139,11 +139,47 @@ impl TurbulenceControls {
     }
 }
 
+/// How the flow direction is read from the scalar noise.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum FieldMode {
+    /// The noise value times pi is the angle.
+    Value,
+    /// Uphill, along the noise gradient.
+    Gradient,
+    /// Across the gradient, following the isolines; the curl of the noise
+    /// as a stream function, so the flow is divergence free.
+    #[serde(alias = "Curl")]
+    Contour,
+}
+
+impl std::fmt::Display for FieldMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                FieldMode::Value => "Value",
+                FieldMode::Gradient => "Gradient",
+                FieldMode::Contour => "Contour",
+            }
+        )
+    }
+}
+
+fn default_field_mode() -> Option<FieldMode> {
+    Some(FieldMode::Value)
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub struct NoiseControls {
     pub noise_function: Option<NoiseFunction>,
     pub noise_factor: f32,
     pub noise_scale: f32,
+    #[serde(default = "default_field_mode")]
+    pub field_mode: Option<FieldMode>,
+    /// Turn the flow around, so curves run the other way.
+    #[serde(default)]
+    pub reverse: bool,
 }
 
 impl NoiseControls {
@@ -152,6 +188,8 @@ impl NoiseControls {
             noise_function: Some(noise_function),
             noise_factor,
             noise_scale,
+            field_mode: Some(FieldMode::Value),
+            reverse: false,
         }
     }
 
@@ -177,6 +215,8 @@ impl Default for NoiseControls {
             noise_function: Some(NoiseFunction::Fbm),
             noise_factor: 1.0,
             noise_scale: 4.0,
+            field_mode: Some(FieldMode::Value),
+            reverse: false,
         }
     }
 }
@@ -0,0 +1,208 @@
+//! Drop shadows and bevel lighting for the curves, computed from the alpha
+//! of each rendered curve layer, for a cut-paper or relief look. Later
+//! layers cast their shadows over the ones composited before them.
+
+use crate::effects::blur;
+use crate::gui::{color_picker, section, SliderRow};
+use eframe::egui;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use wassily::prelude::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ReliefControls {
+    pub shadow: bool,
+    /// Shadow offset in pixels.
+    pub shadow_x: f32,
+    pub shadow_y: f32,
+    /// Shadow softness in pixels.
+    pub shadow_blur: f32,
+    pub shadow_color: egui::Color32,
+    pub shadow_opacity: f32,
+    pub emboss: bool,
+    /// Direction the light comes from, in degrees; 0 is from the right,
+    /// 90 from below.
+    pub light_angle: f32,
+    /// How steep the bevel looks.
+    pub depth: f32,
+    /// Width of the bevel in pixels.
+    pub bevel: f32,
+}
+
+impl Default for ReliefControls {
+    fn default() -> Self {
+        Self {
+            shadow: false,
+            shadow_x: 4.0,
+            shadow_y: 4.0,
+            shadow_blur: 4.0,
+            shadow_color: egui::Color32::BLACK,
+            shadow_opacity: 0.5,
+            emboss: false,
+            light_angle: 225.0,
+            depth: 2.0,
+            bevel: 3.0,
+        }
+    }
+}
+
+impl ReliefControls {
+    pub fn ui(&mut self, ui: &mut egui::Ui) {
+        section(ui, "Relief");
+        egui::Grid::new("relief")
+            .spacing((15.0, 10.0))
+            .min_col_width(90.0)
+            .show(ui, |ui| {
+                ui.label("Shadow").on_hover_ui(|ui| {
+                    ui.colored_label(egui::Color32::ORANGE, "Drop shadows cast by the");
+                    ui.colored_label(egui::Color32::ORANGE, "curves onto what is below.");
+                });
+                ui.checkbox(&mut self.shadow, "");
+                ui.end_row();
+                if self.shadow {
+                    SliderRow::new("Offset X", &mut self.shadow_x, 4.0, -50.0..=50.0)
+                        .steps(0.5, 5.0)
+                        .decimals(1)
+                        .show(ui);
+                    SliderRow::new("Offset Y", &mut self.shadow_y, 4.0, -50.0..=50.0)
+                        .steps(0.5, 5.0)
+                        .decimals(1)
+                        .show(ui);
+                    SliderRow::new("Blur", &mut self.shadow_blur, 4.0, 0.0..=30.0)
+                        .hover(&["Shadow softness in pixels."])
+                        .steps(0.5, 2.0)
+                        .decimals(1)
+                        .show(ui);
+                    SliderRow::new("Shadow Opacity", &mut self.shadow_opacity, 0.5, 0.0..=1.0)
+                        .steps(0.05, 0.1)
+                        .decimals(2)
+                        .show(ui);
+                    color_picker(ui, "Shadow Color", &mut self.shadow_color);
+                }
+                ui.label("Emboss").on_hover_ui(|ui| {
+                    ui.colored_label(egui::Color32::ORANGE, "Light the curves as if they");
+                    ui.colored_label(egui::Color32::ORANGE, "were raised from the page.");
+                });
+                ui.checkbox(&mut self.emboss, "");
+                ui.end_row();
+                if self.emboss {
+                    SliderRow::new("Light Angle", &mut self.light_angle, 225.0, 0.0..=360.0)
+                        .hover(&["Where the light comes from;", "0 is the right, 90 below."])
+                        .steps(5.0, 15.0)
+                        .show(ui);
+                    SliderRow::new("Depth", &mut self.depth, 2.0, 0.0..=10.0)
+                        .hover(&["How steep the bevel looks."])
+                        .steps(0.1, 1.0)
+                        .decimals(1)
+                        .show(ui);
+                    SliderRow::new("Bevel", &mut self.bevel, 3.0, 0.5..=20.0)
+                        .hover(&["Width of the bevel in pixels."])
+                        .steps(0.5, 2.0)
+                        .decimals(1)
+                        .show(ui);
+                }
+            });
+    }
+
+    /// Light and shadow a transparent layer of curves in place; lengths are
+    /// multiplied by `scale`.
+    pub fn apply(&self, pixmap: &mut Pixmap, scale: f32) {
+        if !self.shadow && !self.emboss {
+            return;
+        }
+        let (w, h) = (pixmap.width() as usize, pixmap.height() as usize);
+        let mut img: Vec<[f32; 4]> = pixmap
+            .pixels()
+            .par_iter()
+            .map(|p| {
+                [
+                    p.red() as f32 / 255.0,
+                    p.green() as f32 / 255.0,
+                    p.blue() as f32 / 255.0,
+                    p.alpha() as f32 / 255.0,
+                ]
+            })
+            .collect();
+
+        if self.emboss {
+            // The blurred alpha is a height map; each pixel is lit by how
+            // far its slope turns toward the light, relative to flat ground.
+            let heights: Vec<[f32; 4]> = img.iter().map(|c| [c[3]; 4]).collect();
+            let heights = blur(heights, w, h, (self.bevel * scale).round() as usize);
+            let height = |i: usize, j: usize| heights[j.min(h - 1) * w + i.min(w - 1)][3];
+            let (ly, lx) = self.light_angle.to_radians().sin_cos();
+            // Light from 45 degrees above the page.
+            let (lx, ly, lz) = (lx * 0.707, ly * 0.707, 0.707f32);
+            // The blur spreads each edge over the bevel width; scaling the
+            // slopes back by it leaves `depth` alone setting the steepness.
+            let k = self.depth * self.bevel.max(0.5) * scale;
+            img.par_chunks_mut(w).enumerate().for_each(|(j, row)| {
+                for (i, c) in row.iter_mut().enumerate() {
+                    if c[3] <= 0.0 {
+                        continue;
+                    }
+                    let dx = 0.5 * k * (height(i + 1, j) - height(i.saturating_sub(1), j));
+                    let dy = 0.5 * k * (height(i, j + 1) - height(i, j.saturating_sub(1)));
+                    let len = (dx * dx + dy * dy + 1.0).sqrt();
+                    let shade = (-dx * lx - dy * ly + lz) / len - lz;
+                    let a = c[3];
+                    for v in c.iter_mut().take(3) {
+                        *v = if shade > 0.0 {
+                            *v + (a - *v) * shade
+                        } else {
+                            *v * (1.0 + shade)
+                        };
+                    }
+                }
+            });
+        }
+
+        if self.shadow {
+            let sc = self.shadow_color;
+            let tint = [
+                sc.r() as f32 / 255.0,
+                sc.g() as f32 / 255.0,
+                sc.b() as f32 / 255.0,
+                1.0,
+            ];
+            let (ox, oy) = (
+                (self.shadow_x * scale).round() as isize,
+                (self.shadow_y * scale).round() as isize,
+            );
+            // The layer's silhouette, moved by the offset and tinted.
+            let mut shadow = vec![[0.0f32; 4]; w * h];
+            shadow.par_chunks_mut(w).enumerate().for_each(|(j, row)| {
+                let sj = j as isize - oy;
+                if sj < 0 || sj >= h as isize {
+                    return;
+                }
+                for (i, px) in row.iter_mut().enumerate() {
+                    let si = i as isize - ox;
+                    if si >= 0 && si < w as isize {
+                        let a = img[sj as usize * w + si as usize][3] * self.shadow_opacity;
+                        *px = tint.map(|t| t * a);
+                    }
+                }
+            });
+            let shadow = blur(shadow, w, h, (self.shadow_blur * scale).round() as usize);
+            // The curves over their shadow.
+            img.par_iter_mut().zip(&shadow).for_each(|(c, s)| {
+                let a = c[3];
+                for (v, s) in c.iter_mut().zip(s) {
+                    *v += s * (1.0 - a);
+                }
+            });
+        }
+
+        pixmap
+            .pixels_mut()
+            .par_iter_mut()
+            .zip(&img)
+            .for_each(|(px, c)| {
+                let a = c[3].clamp(0.0, 1.0);
+                let q = |v: f32| (v.clamp(0.0, a) * 255.0 + 0.5) as u8;
+                *px = PremultipliedColorU8::from_rgba(q(c[0]), q(c[1]), q(c[2]), q(a))
+                    .unwrap_or(*px);
+            });
+    }
+}
@@ -0,0 +1,142 @@
+//! The Rings style: closed shapes instead of open curves. Each seed gets a
+//! circle or polygon, optionally nested, whose vertices are carried along
+//! the flow for a few steps, so the rings wobble and marble with the field.
+
+use crate::field::Field;
+use crate::gui::{color_picker, pick_list, section, SliderRow, SPACE};
+use crate::size::{SizeControls, SizeFn};
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+use wassily::prelude::*;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum RingShape {
+    Circle,
+    Polygon,
+}
+
+impl std::fmt::Display for RingShape {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                RingShape::Circle => "Circle",
+                RingShape::Polygon => "Polygon",
+            }
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RingControls {
+    pub shape: Option<RingShape>,
+    /// Polygon: the number of corners.
+    pub sides: u32,
+    /// The outer radius at each seed.
+    pub size_controls: SizeControls,
+    /// Concentric rings per seed, shrinking evenly toward the center.
+    pub rings: u32,
+    /// Flow steps each vertex is carried; 0 leaves the shapes undeformed.
+    pub steps: u32,
+    pub fill: bool,
+    pub stroke: bool,
+    /// The stroke color when the rings are also filled; unfilled rings are
+    /// stroked in the curve color.
+    pub stroke_color: egui::Color32,
+}
+
+impl Default for RingControls {
+    fn default() -> Self {
+        Self {
+            shape: Some(RingShape::Circle),
+            sides: 6,
+            size_controls: SizeControls::default()
+                .set_size_fn(Some(SizeFn::Constant))
+                .set_size(30.0)
+                .set_min_size(5.0),
+            rings: 1,
+            steps: 10,
+            fill: true,
+            stroke: true,
+            stroke_color: egui::Color32::BLACK,
+        }
+    }
+}
+
+impl RingControls {
+    pub fn ui(&mut self, ui: &mut egui::Ui) {
+        use RingShape::*;
+        section(ui, "Rings");
+        egui::Grid::new("rings")
+            .spacing((15.0, 10.0))
+            .min_col_width(90.0)
+            .show(ui, |ui| {
+                pick_list(ui, "Shape", &[Circle, Polygon], &mut self.shape);
+                if self.shape == Some(Polygon) {
+                    SliderRow::new("Sides", &mut self.sides, 6, 3..=12).show(ui);
+                }
+                SliderRow::new("Rings", &mut self.rings, 1, 1..=20)
+                    .hover(&["Concentric rings per seed."])
+                    .show(ui);
+                SliderRow::new("Steps", &mut self.steps, 10, 0..=100)
+                    .hover(&["Flow steps each vertex is", "carried; 0 is undeformed."])
+                    .show(ui);
+                ui.label("Fill");
+                ui.checkbox(&mut self.fill, "");
+                ui.end_row();
+                ui.label("Stroke");
+                ui.checkbox(&mut self.stroke, "");
+                ui.end_row();
+                if self.fill && self.stroke {
+                    color_picker(ui, "Stroke Color", &mut self.stroke_color);
+                }
+            });
+        ui.add_space(SPACE);
+        self.size_controls.ui(ui);
+    }
+
+    /// The closed outlines around `c` with outer radius `r`, outermost
+    /// first, each with its first vertex repeated, after advection.
+    pub fn shapes(&self, flow: &Field, c: Point, r: f32) -> Vec<Vec<Point>> {
+        let count = self.rings.max(1);
+        (0..count)
+            .map(|k| {
+                let rk = r * (count - k) as f32 / count as f32;
+                // About one vertex per step length around the ring, so the
+                // edges deform as well as the corners.
+                let per_step = |len: f32| (len / flow.step_size.max(1.0)) as usize;
+                let base: Vec<Point> = match self.shape {
+                    Some(RingShape::Polygon) => {
+                        let sides = self.sides.max(3) as usize;
+                        let corner = |j: usize| {
+                            let t = TAU * j as f32 / sides as f32;
+                            pt(c.x + rk * t.cos(), c.y + rk * t.sin())
+                        };
+                        let per = per_step(corner(0).dist2(corner(1)).sqrt()).clamp(1, 60);
+                        (0..sides * per)
+                            .map(|j| {
+                                let (a, b) = (corner(j / per), corner(j / per + 1));
+                                let t = (j % per) as f32 / per as f32;
+                                pt(a.x + (b.x - a.x) * t, a.y + (b.y - a.y) * t)
+                            })
+                            .collect()
+                    }
+                    _ => {
+                        let n = per_step(TAU * rk).clamp(16, 720);
+                        (0..n)
+                            .map(|j| {
+                                let t = TAU * j as f32 / n as f32;
+                                pt(c.x + rk * t.cos(), c.y + rk * t.sin())
+                            })
+                            .collect()
+                    }
+                };
+                let mut outline: Vec<Point> =
+                    base.into_iter().map(|p| flow.advect(p, self.steps)).collect();
+                outline.push(outline[0]);
+                outline
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,182 @@
+//! Smoothing for Line curves: Douglas–Peucker simplification to drop the
+//! integration steps that add nothing, then a cubic spline through what is
+//! left, so curves stay smooth when printed large. Also arc-length
+//! resampling, for evenly spaced dots.
+
+use serde::{Deserialize, Serialize};
+use wassily::prelude::*;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Smoothing {
+    Off,
+    /// A centripetal Catmull-Rom spline through every point.
+    CatmullRom,
+    /// An approximating cubic Bézier (uniform B-spline) spline that irons
+    /// out step-to-step jitter; it passes through the ends only.
+    Bezier,
+}
+
+impl std::fmt::Display for Smoothing {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Smoothing::Off => "Off",
+                Smoothing::CatmullRom => "Catmull-Rom",
+                Smoothing::Bezier => "Bézier",
+            }
+        )
+    }
+}
+
+/// The indices of `pts` that Douglas–Peucker keeps at tolerance `eps`,
+/// always including both ends.
+pub fn simplify(pts: &[Point], eps: f32) -> Vec<usize> {
+    let n = pts.len();
+    if n < 3 || eps <= 0.0 {
+        return (0..n).collect();
+    }
+    let mut keep = vec![false; n];
+    keep[0] = true;
+    keep[n - 1] = true;
+    let mut stack = vec![(0, n - 1)];
+    while let Some((a, b)) = stack.pop() {
+        let (pa, pb) = (pts[a], pts[b]);
+        let (dx, dy) = (pb.x - pa.x, pb.y - pa.y);
+        let len = (dx * dx + dy * dy).sqrt();
+        let mut far = (0.0, a);
+        for (i, p) in pts.iter().enumerate().take(b).skip(a + 1) {
+            let d = if len < f32::EPSILON {
+                p.dist2(pa).sqrt()
+            } else {
+                ((p.x - pa.x) * dy - (p.y - pa.y) * dx).abs() / len
+            };
+            if d > far.0 {
+                far = (d, i);
+            }
+        }
+        if far.0 > eps {
+            keep[far.1] = true;
+            stack.push((a, far.1));
+            stack.push((far.1, b));
+        }
+    }
+    (0..n).filter(|&i| keep[i]).collect()
+}
+
+fn lerp2(a: Point, b: Point, t: f32) -> Point {
+    pt(a.x + (b.x - a.x) * t, a.y + (b.y - a.y) * t)
+}
+
+fn bezier(p0: Point, p1: Point, p2: Point, p3: Point, t: f32) -> Point {
+    let s = 1.0 - t;
+    let (a, b, c, d) = (s * s * s, 3.0 * s * s * t, 3.0 * s * t * t, t * t * t);
+    pt(
+        a * p0.x + b * p1.x + c * p2.x + d * p3.x,
+        a * p0.y + b * p1.y + c * p2.y + d * p3.y,
+    )
+}
+
+/// Bézier handles for the centripetal Catmull-Rom segment from `p1` to `p2`.
+fn catmull_handles(p0: Point, p1: Point, p2: Point, p3: Point) -> (Point, Point) {
+    let d = |a: Point, b: Point| a.dist2(b).sqrt().sqrt().max(1e-4);
+    let (d1, d2, d3) = (d(p0, p1), d(p1, p2), d(p2, p3));
+    let handle = |pa: Point, pb: Point, pc: Point, da: f32, db: f32| {
+        let k = 2.0 * da * da + 3.0 * da * db + db * db;
+        let m = 3.0 * da * (da + db);
+        pt(
+            (da * da * pc.x - db * db * pa.x + k * pb.x) / m,
+            (da * da * pc.y - db * db * pa.y + k * pb.y) / m,
+        )
+    };
+    (handle(p0, p1, p2, d1, d2), handle(p3, p2, p1, d3, d2))
+}
+
+/// Simplify `pts` at tolerance `eps`, then sample the chosen spline
+/// `samples` times per remaining segment. Alongside each output point is
+/// its position in `pts` as a fractional index, for carrying per-point
+/// widths and colors across.
+pub fn smooth(
+    pts: &[Point],
+    smoothing: Smoothing,
+    eps: f32,
+    samples: u32,
+) -> (Vec<Point>, Vec<f32>) {
+    let kept = simplify(pts, eps);
+    let ctrl: Vec<Point> = kept.iter().map(|&i| pts[i]).collect();
+    let n = ctrl.len();
+    if smoothing == Smoothing::Off || n < 3 {
+        return (ctrl, kept.iter().map(|&i| i as f32).collect());
+    }
+    let samples = samples.max(1);
+    let mut out = Vec::with_capacity((n - 1) * samples as usize + 1);
+    let mut at = Vec::with_capacity(out.capacity());
+    // Phantom end points, reflected, so the spline reaches both ends.
+    let get = |i: isize| -> Point {
+        if i < 0 {
+            lerp2(ctrl[1], ctrl[0], 2.0)
+        } else if i as usize >= n {
+            lerp2(ctrl[n - 2], ctrl[n - 1], 2.0)
+        } else {
+            ctrl[i as usize]
+        }
+    };
+    for i in 0..n - 1 {
+        let j = i as isize;
+        let (p0, p1, p2, p3) = (get(j - 1), get(j), get(j + 1), get(j + 2));
+        let (b0, b1, b2, b3) = match smoothing {
+            Smoothing::CatmullRom => {
+                let (h1, h2) = catmull_handles(p0, p1, p2, p3);
+                (p1, h1, h2, p2)
+            }
+            // The uniform B-spline segment in Bézier form.
+            _ => {
+                let (h1, h2) = (lerp2(p1, p2, 1.0 / 3.0), lerp2(p2, p1, 1.0 / 3.0));
+                (
+                    lerp2(lerp2(p0, p1, 2.0 / 3.0), h1, 0.5),
+                    h1,
+                    h2,
+                    lerp2(h2, lerp2(p3, p2, 2.0 / 3.0), 0.5),
+                )
+            }
+        };
+        let (k0, k1) = (kept[i] as f32, kept[i + 1] as f32);
+        for s in 0..samples {
+            let t = s as f32 / samples as f32;
+            out.push(bezier(b0, b1, b2, b3, t));
+            at.push(k0 + (k1 - k0) * t);
+        }
+    }
+    // With reflected phantoms both splines end exactly on the last point.
+    out.push(ctrl[n - 1]);
+    at.push(kept[n - 1] as f32);
+    (out, at)
+}
+
+/// Points every `d` along the polyline `pts`, starting at the first, with
+/// their positions in `pts` as fractional indices like `smooth`.
+pub fn resample(pts: &[Point], d: f32) -> (Vec<Point>, Vec<f32>) {
+    let mut out = Vec::new();
+    let mut at = Vec::new();
+    if pts.is_empty() || d <= 0.0 {
+        return (out, at);
+    }
+    out.push(pts[0]);
+    at.push(0.0);
+    // Distance still to go before the next point.
+    let mut need = d;
+    for i in 0..pts.len() - 1 {
+        let seg = pts[i].dist2(pts[i + 1]).sqrt();
+        let mut s = 0.0;
+        while seg - s >= need {
+            s += need;
+            let t = s / seg;
+            out.push(lerp2(pts[i], pts[i + 1], t));
+            at.push(i as f32 + t);
+            need = d;
+        }
+        need -= seg - s;
+    }
+    (out, at)
+}
@@ -0,0 +1,147 @@
+//! Optional rules that end a curve early: running into an earlier curve,
+//! closing a loop on itself, stalling in a vortex, or leaving a mask. They
+//! apply to every location except Even, which has its own spacing rule.
+
+use crate::gui::{pick_list, section, SliderRow};
+use crate::imgnoise::FieldImage;
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+use wassily::prelude::*;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Mask {
+    Off,
+    /// The ellipse inscribed in the canvas.
+    Ellipse,
+    /// Where the noise image, through its color map, is above a threshold.
+    Image,
+}
+
+impl std::fmt::Display for Mask {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Mask::Off => "Off",
+                Mask::Ellipse => "Ellipse",
+                Mask::Image => "Image",
+            }
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct StopControls {
+    /// Stop a curve when it comes within `gap` separations of an earlier one.
+    pub collide: bool,
+    pub gap: f32,
+    /// Stop a curve when it runs back into itself.
+    pub loops: bool,
+    /// Stop a curve when it stops making headway, circling in a vortex.
+    pub stall: bool,
+    pub mask: Option<Mask>,
+    /// Image mask: the color map value, in [0, 1], curves must stay above.
+    pub threshold: f32,
+}
+
+impl Default for StopControls {
+    fn default() -> Self {
+        Self {
+            collide: false,
+            gap: 0.5,
+            loops: false,
+            stall: false,
+            mask: Some(Mask::Off),
+            threshold: 0.5,
+        }
+    }
+}
+
+impl StopControls {
+    pub fn ui(&mut self, ui: &mut egui::Ui) {
+        section(ui, "Termination");
+        egui::Grid::new("stops")
+            .spacing((15.0, 10.0))
+            .min_col_width(90.0)
+            .show(ui, |ui| {
+                ui.label("Collide").on_hover_ui(|ui| {
+                    ui.colored_label(egui::Color32::ORANGE, "End curves where they come");
+                    ui.colored_label(egui::Color32::ORANGE, "close to an earlier curve.");
+                });
+                ui.checkbox(&mut self.collide, "");
+                ui.end_row();
+                if self.collide {
+                    SliderRow::new("Gap", &mut self.gap, 0.5, 0.1..=1.0)
+                        .hover(&["Closest approach, as a", "fraction of the separation."])
+                        .steps(0.05, 0.1)
+                        .decimals(2)
+                        .show(ui);
+                }
+                ui.label("Loops").on_hover_ui(|ui| {
+                    ui.colored_label(egui::Color32::ORANGE, "End curves that run back");
+                    ui.colored_label(egui::Color32::ORANGE, "into themselves.");
+                });
+                ui.checkbox(&mut self.loops, "");
+                ui.end_row();
+                ui.label("Stall").on_hover_ui(|ui| {
+                    ui.colored_label(egui::Color32::ORANGE, "End curves that stop making");
+                    ui.colored_label(egui::Color32::ORANGE, "headway in a vortex.");
+                });
+                ui.checkbox(&mut self.stall, "");
+                ui.end_row();
+                pick_list(
+                    ui,
+                    "Mask",
+                    &[Mask::Off, Mask::Ellipse, Mask::Image],
+                    &mut self.mask,
+                );
+                if self.mask == Some(Mask::Image) {
+                    SliderRow::new("Threshold", &mut self.threshold, 0.5, 0.0..=1.0)
+                        .hover(&["Curves stay where the image", "is above this value."])
+                        .steps(0.05, 0.1)
+                        .decimals(2)
+                        .show(ui);
+                }
+            });
+    }
+}
+
+/// The render-time termination rules a `Field` checks on every step.
+pub struct Stops {
+    pub loops: bool,
+    pub stall: bool,
+    pub mask: Mask,
+    pub threshold: f32,
+    /// Image mask: the processed noise image, if there is one.
+    pub image: Option<FieldImage>,
+}
+
+impl Stops {
+    pub fn off() -> Self {
+        Self {
+            loops: false,
+            stall: false,
+            mask: Mask::Off,
+            threshold: 0.5,
+            image: None,
+        }
+    }
+
+    /// True if (x, y) is inside the mask on a `w` by `h` canvas.
+    pub fn inside(&self, x: f32, y: f32, w: f32, h: f32) -> bool {
+        match self.mask {
+            Mask::Off => true,
+            Mask::Ellipse => {
+                let dx = (x - w / 2.0) / (w / 2.0);
+                let dy = (y - h / 2.0) / (h / 2.0);
+                dx * dx + dy * dy <= 1.0
+            }
+            // With no image there is nothing to mask against.
+            Mask::Image => self
+                .image
+                .as_ref()
+                .is_none_or(|img| 0.5 * img.value(x, y) + 0.5 >= self.threshold),
+        }
+    }
+}
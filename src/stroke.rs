@@ -0,0 +1,198 @@
+//! Variable-width strokes for the Line style. Curves are drawn as filled
+//! outline polygons rather than stroked polylines, so their width can taper
+//! at the ends, follow a size function or the field magnitude, or swell and
+//! thin like a broad calligraphy nib, and still look clean at print scale.
+//! Any of them can be simplified and smoothed first, see `spline`.
+
+use crate::gui::{pick_list, section, SliderRow};
+use crate::size::SizeControls;
+use crate::spline::Smoothing;
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+use wassily::prelude::*;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum StrokeMode {
+    /// A constant width stroked polyline.
+    Plain,
+    /// A filled ribbon whose width varies along the curve.
+    Ribbon,
+    /// A ribbon swept by a flat nib held at a fixed angle.
+    Calligraphic,
+}
+
+impl std::fmt::Display for StrokeMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                StrokeMode::Plain => "Plain",
+                StrokeMode::Ribbon => "Ribbon",
+                StrokeMode::Calligraphic => "Calligraphic",
+            }
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct StrokeControls {
+    pub mode: Option<StrokeMode>,
+    /// Fraction of the curve length over which the width swells in.
+    pub taper_in: f32,
+    /// Fraction of the curve length over which the width thins out.
+    pub taper_out: f32,
+    /// Take the width from the size function instead of the stroke width.
+    pub follow_size: bool,
+    pub size_controls: SizeControls,
+    /// Calligraphic: nib angle in degrees, 0 is horizontal.
+    pub nib_angle: f32,
+    /// Calligraphic: the thinnest stroke, as a fraction of the full width.
+    pub nib_min: f32,
+    /// A spline fitted through the points before drawing.
+    pub smoothing: Option<Smoothing>,
+    /// Douglas–Peucker tolerance in pixels; 0 keeps every point.
+    pub simplify: f32,
+    /// Spline samples per remaining segment.
+    pub samples: u32,
+}
+
+impl Default for StrokeControls {
+    fn default() -> Self {
+        Self {
+            mode: Some(StrokeMode::Plain),
+            taper_in: 0.2,
+            taper_out: 0.2,
+            follow_size: false,
+            size_controls: SizeControls::default().set_size(20.0).set_min_size(1.0),
+            nib_angle: 45.0,
+            nib_min: 0.1,
+            smoothing: Some(Smoothing::Off),
+            simplify: 0.0,
+            samples: 4,
+        }
+    }
+}
+
+impl StrokeControls {
+    pub fn ui(&mut self, ui: &mut egui::Ui) {
+        use StrokeMode::*;
+        section(ui, "Stroke");
+        egui::Grid::new("stroke_mode")
+            .spacing((15.0, 10.0))
+            .min_col_width(90.0)
+            .show(ui, |ui| {
+                pick_list(ui, "Mode", &[Plain, Ribbon, Calligraphic], &mut self.mode);
+                pick_list(
+                    ui,
+                    "Smoothing",
+                    &[Smoothing::Off, Smoothing::CatmullRom, Smoothing::Bezier],
+                    &mut self.smoothing,
+                );
+                SliderRow::new("Simplify", &mut self.simplify, 0.0, 0.0..=5.0)
+                    .hover(&["Drop points closer than this many", "pixels to the line; 0 is off."])
+                    .steps(0.1, 0.5)
+                    .decimals(1)
+                    .show(ui);
+                if self.smoothing != Some(Smoothing::Off) {
+                    SliderRow::new("Samples", &mut self.samples, 4, 1..=16)
+                        .hover(&["Spline points drawn per", "remaining segment."])
+                        .show(ui);
+                }
+                if self.mode != Some(Plain) {
+                    SliderRow::new("Taper In", &mut self.taper_in, 0.2, 0.0..=1.0)
+                        .hover(&["Fraction of the curve over", "which the width swells in."])
+                        .steps(0.05, 0.1)
+                        .decimals(2)
+                        .show(ui);
+                    SliderRow::new("Taper Out", &mut self.taper_out, 0.2, 0.0..=1.0)
+                        .hover(&["Fraction of the curve over", "which the width thins out."])
+                        .steps(0.05, 0.1)
+                        .decimals(2)
+                        .show(ui);
+                    if self.mode == Some(Calligraphic) {
+                        SliderRow::new("Nib Angle", &mut self.nib_angle, 45.0, 0.0..=180.0)
+                            .hover(&["Angle of the flat nib;", "0 is horizontal."])
+                            .steps(5.0, 15.0)
+                            .show(ui);
+                        SliderRow::new("Nib Min", &mut self.nib_min, 0.1, 0.0..=1.0)
+                            .hover(&["Thinnest stroke, as a", "fraction of the full width."])
+                            .steps(0.05, 0.1)
+                            .decimals(2)
+                            .show(ui);
+                    }
+                    ui.label("Follow Size").on_hover_ui(|ui| {
+                        ui.colored_label(egui::Color32::ORANGE, "Take the width from the size");
+                        ui.colored_label(egui::Color32::ORANGE, "function, not Stroke Width.");
+                    });
+                    ui.checkbox(&mut self.follow_size, "");
+                    ui.end_row();
+                }
+            });
+        if self.mode != Some(StrokeMode::Plain) && self.follow_size {
+            self.size_controls.ui(ui);
+        }
+    }
+
+    /// The taper multiplier at arc length `s` of a curve of length `len`.
+    fn taper(&self, s: f32, len: f32) -> f32 {
+        let ramp = |d: f32, frac: f32| {
+            if frac <= 0.0 {
+                1.0
+            } else {
+                let t = (d / (frac * len)).clamp(0.0, 1.0);
+                t * t * (3.0 - 2.0 * t)
+            }
+        };
+        ramp(s, self.taper_in) * ramp(len - s, self.taper_out)
+    }
+
+    /// The outline polygon of a ribbon along `pts` whose full width at
+    /// point i is `width(i)`, before tapering: the left edge out, then the
+    /// right edge back.
+    pub fn outline(&self, pts: &[Point], width: impl Fn(usize) -> f32) -> Vec<Point> {
+        let n = pts.len();
+        let mut arc = Vec::with_capacity(n);
+        let mut s = 0.0;
+        for i in 0..n {
+            if i > 0 {
+                s += pts[i].dist2(pts[i - 1]).sqrt();
+            }
+            arc.push(s);
+        }
+        let len = s.max(f32::EPSILON);
+        let nib = self.nib_angle.to_radians();
+        let (nib_x, nib_y) = (nib.cos(), nib.sin());
+        let nib_min = self.nib_min.clamp(0.0, 1.0);
+        let mut left = Vec::with_capacity(n);
+        let mut right = Vec::with_capacity(n);
+        for i in 0..n {
+            let prev = pts[i.saturating_sub(1)];
+            let next = pts[(i + 1).min(n - 1)];
+            let (tx, ty) = (next.x - prev.x, next.y - prev.y);
+            let tl = (tx * tx + ty * ty).sqrt().max(f32::EPSILON);
+            let (nx, ny) = (-ty / tl, tx / tl);
+            let half = 0.5 * width(i) * self.taper(arc[i], len);
+            // A calligraphic nib offsets along its own fixed direction, so
+            // the ribbon is widest across the nib and thins to `nib_min`
+            // running along it. The nib is turned to the normal's side, or
+            // the two edges would swap and the ribbon twist, and is topped
+            // up along the normal where its projection falls below
+            // `nib_min`.
+            let (ox, oy) = if self.mode == Some(StrokeMode::Calligraphic) {
+                let proj = nib_x * nx + nib_y * ny;
+                let s = proj.signum();
+                let lift = (nib_min - proj.abs()).max(0.0);
+                (s * nib_x + lift * nx, s * nib_y + lift * ny)
+            } else {
+                (nx, ny)
+            };
+            let p = pts[i];
+            left.push(pt(p.x + half * ox, p.y + half * oy));
+            right.push(pt(p.x - half * ox, p.y - half * oy));
+        }
+        right.reverse();
+        left.extend(right);
+        left
+    }
+}
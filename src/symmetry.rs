@@ -0,0 +1,240 @@
+//! Polar and symmetric flow fields. A `SymmetricNoise` wraps any noise
+//! function and folds its input point into a base region around the canvas
+//! center; the `Field` rotates and mirrors the resulting angles with the
+//! same fold, so streamlines are truly symmetric rather than just sampling
+//! symmetric values.
+
+use crate::gui::{numeric, pick_list, section};
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+use wassily::prelude::*;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum SymmetryMode {
+    Off,
+    Polar,
+    MirrorX,
+    MirrorY,
+    MirrorXY,
+    Rotational,
+    Kaleidoscope,
+}
+
+impl std::fmt::Display for SymmetryMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                SymmetryMode::Off => "Off",
+                SymmetryMode::Polar => "Polar",
+                SymmetryMode::MirrorX => "Mirror X",
+                SymmetryMode::MirrorY => "Mirror Y",
+                SymmetryMode::MirrorXY => "Mirror XY",
+                SymmetryMode::Rotational => "Rotational",
+                SymmetryMode::Kaleidoscope => "Kaleidoscope",
+            }
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SymmetryControls {
+    pub mode: Option<SymmetryMode>,
+    /// Rotational and Kaleidoscope: number of wedges around the center.
+    pub folds: u32,
+}
+
+impl Default for SymmetryControls {
+    fn default() -> Self {
+        Self {
+            mode: Some(SymmetryMode::Off),
+            folds: 6,
+        }
+    }
+}
+
+impl SymmetryControls {
+    pub fn ui(&mut self, ui: &mut egui::Ui) {
+        use SymmetryMode::*;
+        section(ui, "Symmetry");
+        egui::Grid::new("symmetry")
+            .spacing((15.0, 10.0))
+            .min_col_width(90.0)
+            .show(ui, |ui| {
+                pick_list(
+                    ui,
+                    "Mode",
+                    &[
+                        Off,
+                        Polar,
+                        MirrorX,
+                        MirrorY,
+                        MirrorXY,
+                        Rotational,
+                        Kaleidoscope,
+                    ],
+                    &mut self.mode,
+                );
+                if matches!(self.mode, Some(Rotational) | Some(Kaleidoscope)) {
+                    numeric(ui, "Folds", &mut self.folds, 6, 2..=24, 1.0, 0);
+                }
+            });
+    }
+}
+
+/// How a folded point's field angle maps back to the canvas: rotate by
+/// `rotation`, after negating the angle if the fold mirrored the point.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Frame {
+    pub rotation: f32,
+    pub mirror: bool,
+}
+
+impl Frame {
+    const IDENTITY: Frame = Frame {
+        rotation: 0.0,
+        mirror: false,
+    };
+
+    pub fn orient(self, theta: f32) -> f32 {
+        self.rotation + if self.mirror { -theta } else { theta }
+    }
+}
+
+/// A symmetry about the canvas center, in canvas units.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Symmetry {
+    pub mode: SymmetryMode,
+    pub folds: u32,
+    pub cx: f32,
+    pub cy: f32,
+    /// Polar: canvas units of the angular coordinate per radian, so one
+    /// turn around the center spans about one canvas side of noise.
+    pub arc: f32,
+}
+
+impl Symmetry {
+    pub fn new(controls: &SymmetryControls, w: u32, h: u32) -> Self {
+        Self {
+            mode: controls.mode.unwrap_or(SymmetryMode::Off),
+            folds: controls.folds.max(2),
+            cx: w as f32 / 2.0,
+            cy: h as f32 / 2.0,
+            arc: 0.5 * (w + h) as f32 / TAU,
+        }
+    }
+
+    pub fn is_off(&self) -> bool {
+        self.mode == SymmetryMode::Off
+    }
+
+    /// The point the field is actually sampled at for canvas point (x, y),
+    /// and the frame that maps the sampled angle back to (x, y).
+    pub fn fold(&self, x: f32, y: f32) -> (f32, f32, Frame) {
+        let (mut dx, mut dy) = (x - self.cx, y - self.cy);
+        let frame = match self.mode {
+            SymmetryMode::Off => Frame::IDENTITY,
+            // The field is laid out over (radius, arc length) and its angles
+            // are read relative to the radial direction. The angular
+            // coordinate is cut along the negative x-axis.
+            SymmetryMode::Polar => {
+                let phi = dy.atan2(dx);
+                let r = (dx * dx + dy * dy).sqrt();
+                return (
+                    self.cx + r,
+                    self.cy + phi * self.arc,
+                    Frame {
+                        rotation: phi,
+                        mirror: false,
+                    },
+                );
+            }
+            SymmetryMode::MirrorX => {
+                if dx > 0.0 {
+                    dx = -dx;
+                    Frame {
+                        rotation: PI,
+                        mirror: true,
+                    }
+                } else {
+                    Frame::IDENTITY
+                }
+            }
+            SymmetryMode::MirrorY => {
+                if dy > 0.0 {
+                    dy = -dy;
+                    Frame {
+                        rotation: 0.0,
+                        mirror: true,
+                    }
+                } else {
+                    Frame::IDENTITY
+                }
+            }
+            SymmetryMode::MirrorXY => {
+                // Reflect in x, then in y: the y fold negates the sampled
+                // angle before the x fold maps it through pi - theta.
+                let mut frame = Frame::IDENTITY;
+                if dx > 0.0 {
+                    dx = -dx;
+                    frame = Frame {
+                        rotation: PI,
+                        mirror: true,
+                    };
+                }
+                if dy > 0.0 {
+                    dy = -dy;
+                    frame = Frame {
+                        rotation: frame.rotation,
+                        mirror: !frame.mirror,
+                    };
+                }
+                frame
+            }
+            SymmetryMode::Rotational | SymmetryMode::Kaleidoscope => {
+                let wedge = TAU / self.folds as f32;
+                let phi = dy.atan2(dx).rem_euclid(TAU);
+                let k = (phi / wedge).floor();
+                let mut psi = phi - k * wedge;
+                let mut frame = Frame {
+                    rotation: k * wedge,
+                    mirror: false,
+                };
+                // Kaleidoscope wedges are mirrored halves: the far half
+                // reflects across the wedge bisector onto the near one.
+                if self.mode == SymmetryMode::Kaleidoscope && psi > 0.5 * wedge {
+                    psi = wedge - psi;
+                    frame = Frame {
+                        rotation: (k + 1.0) * wedge,
+                        mirror: true,
+                    };
+                }
+                let r = (dx * dx + dy * dy).sqrt();
+                dx = r * psi.cos();
+                dy = r * psi.sin();
+                frame
+            }
+        };
+        (self.cx + dx, self.cy + dy, frame)
+    }
+}
+
+/// Any noise function sampled through a `Symmetry`. The noise lives in
+/// noise coordinates, `scale` noise units per canvas unit along each axis,
+/// so points are mapped to the canvas, folded, and mapped back.
+pub struct SymmetricNoise<N> {
+    pub source: N,
+    pub symmetry: Symmetry,
+    pub scale: [f64; 2],
+}
+
+impl<N: NoiseFn<f64, 2>> NoiseFn<f64, 2> for SymmetricNoise<N> {
+    fn get(&self, point: [f64; 2]) -> f64 {
+        let x = (point[0] / self.scale[0]) as f32;
+        let y = (point[1] / self.scale[1]) as f32;
+        let (fx, fy, _) = self.symmetry.fold(x, y);
+        self.source
+            .get([fx as f64 * self.scale[0], fy as f64 * self.scale[1]])
+    }
+}